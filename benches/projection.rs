@@ -0,0 +1,61 @@
+//! Compares the native Web Mercator projection with the `googleprojection` crate it replaced.
+//!
+//! Run with `cargo bench --bench projection`.
+
+use std::{
+    hint::black_box,
+    time::{Duration, Instant},
+};
+
+use bevy_slippy_tiles::{
+    world_coords_to_world_pixel, LatitudeLongitudeCoordinates, TileSize, WebMercatorProjection,
+    ZoomLevel,
+};
+
+const ITERATIONS: u32 = 1_000_000;
+
+fn bench(name: &str, mut f: impl FnMut(u32) -> (f64, f64)) {
+    // Warm up.
+    for i in 0..ITERATIONS / 10 {
+        black_box(f(i));
+    }
+    let started = Instant::now();
+    for i in 0..ITERATIONS {
+        black_box(f(i));
+    }
+    let elapsed = started.elapsed();
+    println!(
+        "{:<40} {:>8.2} ns/iter",
+        name,
+        elapsed.as_nanos() as f64 / ITERATIONS as f64
+    );
+    assert!(elapsed < Duration::from_secs(60));
+}
+
+fn coords(i: u32) -> LatitudeLongitudeCoordinates {
+    LatitudeLongitudeCoordinates {
+        latitude: (i % 170) as f64 - 85.0,
+        longitude: (i % 360) as f64 - 180.0,
+    }
+}
+
+fn main() {
+    let zoom_level = ZoomLevel::L18;
+    bench("googleprojection (new Mercator per call)", |i| {
+        let coords = coords(i);
+        googleprojection::Mercator::with_size(black_box(256))
+            .from_ll_to_subpixel(&(coords.longitude, coords.latitude), 18)
+            .unwrap()
+    });
+    bench("world_coords_to_world_pixel", |i| {
+        world_coords_to_world_pixel(&coords(i), black_box(TileSize::Normal), zoom_level)
+    });
+    let projection = WebMercatorProjection::new(TileSize::Normal, zoom_level);
+    bench("WebMercatorProjection (reused)", |i| {
+        projection.to_pixel(&coords(i))
+    });
+    let projection = WebMercatorProjection::new(TileSize::new(1024), zoom_level);
+    bench("WebMercatorProjection (1024px tiles)", |i| {
+        projection.to_pixel(&coords(i))
+    });
+}
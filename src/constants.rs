@@ -1,7 +1,13 @@
 pub const EARTH_CIRCUMFERENCE: f64 = 40_075_016.686;
 pub const EARTH_RADIUS: f64 = 6_378_137_f64;
+/// Mean radius of the Earth (IUGG), used by the spherical geodesic calculations.
+pub const EARTH_MEAN_RADIUS: f64 = 6_371_008.8;
+/// Flattening of the WGS84 ellipsoid, whose semi-major axis is [`EARTH_RADIUS`].
+pub const WGS84_FLATTENING: f64 = 1.0 / 298.257_223_563;
 pub const DEGREES_PER_METER: f64 = 360.0 / EARTH_CIRCUMFERENCE;
 pub const METERS_PER_DEGREE: f64 = EARTH_CIRCUMFERENCE / 360.0;
+/// Latitude limit of the Web Mercator projection, beyond which there are no tiles.
+pub const MAX_LATITUDE: f64 = 85.051_128_779_806_59;
 
 /// https://wiki.openstreetmap.org/wiki/Slippy_map_tilenames#Resolution_and_Scale
 pub fn meters_per_pixel(
@@ -13,3 +19,14 @@ pub fn meters_per_pixel(
     let latitude_radians = latitude.to_radians();
     base_resolution * latitude_radians.cos() / (1_u32 << zoom_level.to_u8()) as f64
 }
+
+/// The inverse of [`meters_per_pixel`]: the (fractional) zoom level at which a pixel covers `meters_per_pixel` meters
+/// on the ground at the given latitude.
+pub fn zoom_for_meters_per_pixel(
+    meters_per_pixel: f64,
+    latitude: f64,
+    tile_size: crate::types::TileSize,
+) -> f64 {
+    let base_resolution = EARTH_CIRCUMFERENCE / tile_size.to_pixels() as f64;
+    (base_resolution * latitude.to_radians().cos() / meters_per_pixel).log2()
+}
@@ -1,4 +1,7 @@
-use crate::types::{TileSize, ZoomLevel};
+use crate::constants::{DEGREES_PER_METER, MAX_LATITUDE};
+use crate::projection::WebMercatorProjection;
+use crate::tiling::TilingScheme;
+use crate::types::{InvalidCoordinates, TileSize, ZoomLevel};
 use bevy::prelude::Component;
 use std::f64::consts::PI;
 
@@ -12,6 +15,8 @@ pub struct SlippyTileCoordinates {
 
 impl SlippyTileCoordinates {
     /// Get slippy tile coordinates based on a real-world lat/lon and zoom level.
+    ///
+    /// Latitudes are clamped to the Web Mercator limits and longitudes wrap around the antimeridian.
     pub fn from_latitude_longitude(
         lat: f64,
         lon: f64,
@@ -23,6 +28,15 @@ impl SlippyTileCoordinates {
         SlippyTileCoordinates { x, y }
     }
 
+    /// Same as [`SlippyTileCoordinates::from_latitude_longitude`], but fails on NaN or infinite coordinates.
+    pub fn try_from_latitude_longitude(
+        lat: f64,
+        lon: f64,
+        zoom_level: ZoomLevel,
+    ) -> Result<SlippyTileCoordinates, InvalidCoordinates> {
+        Ok(LatitudeLongitudeCoordinates::try_new(lat, lon)?.to_slippy_tile_coordinates(zoom_level))
+    }
+
     /// Get real-world lat/lon based on slippy tile coordinates.
     pub fn to_latitude_longitude(&self, zoom_level: ZoomLevel) -> LatitudeLongitudeCoordinates {
         let zoom = zoom_level.to_u8() as u32;
@@ -35,6 +49,73 @@ impl SlippyTileCoordinates {
     }
 }
 
+/// Fractional slippy tile coordinates, locating a point within its tile.
+///
+/// The integer part is the [`SlippyTileCoordinates`] of the tile, the fractional part is the position within the tile
+/// (from its north-west corner).
+#[derive(Debug, Clone, Copy, PartialEq, Component)]
+pub struct FractionalTileCoordinates {
+    pub x: f64,
+    pub y: f64,
+}
+
+impl FractionalTileCoordinates {
+    /// Get fractional tile coordinates based on a real-world lat/lon and zoom level.
+    ///
+    /// Longitudes wrap around the antimeridian, latitudes are clamped to the Web Mercator limits.
+    pub fn from_latitude_longitude(
+        lat: f64,
+        lon: f64,
+        zoom_level: ZoomLevel,
+    ) -> FractionalTileCoordinates {
+        let tiles = max_tiles_in_dimension(zoom_level);
+        FractionalTileCoordinates {
+            x: (tiles * (lon + 180.0) / 360.0).rem_euclid(tiles),
+            y: latitude_to_tile_y_fraction(lat, zoom_level),
+        }
+    }
+
+    /// Get real-world lat/lon based on fractional tile coordinates.
+    pub fn to_latitude_longitude(&self, zoom_level: ZoomLevel) -> LatitudeLongitudeCoordinates {
+        let tiles = max_tiles_in_dimension(zoom_level);
+        let n = PI * (1.0 - 2.0 * self.y / tiles);
+        LatitudeLongitudeCoordinates {
+            latitude: n.sinh().atan().to_degrees(),
+            longitude: self.x / tiles * 360.0 - 180.0,
+        }
+    }
+
+    /// The tile containing these coordinates (points on the southern edge of the map belong to the last row).
+    pub fn to_slippy_tile_coordinates(&self, zoom_level: ZoomLevel) -> SlippyTileCoordinates {
+        let last = max_tiles_in_dimension(zoom_level) - 1.0;
+        SlippyTileCoordinates {
+            x: self.x.floor().clamp(0.0, last) as u32,
+            y: self.y.floor().clamp(0.0, last) as u32,
+        }
+    }
+
+    /// Pixel offset of these coordinates from the north-west (top left) corner of their tile, y pointing down as in
+    /// the tile image.
+    pub fn pixel_offset(&self, tile_size: TileSize, zoom_level: ZoomLevel) -> (f64, f64) {
+        let tile = self.to_slippy_tile_coordinates(zoom_level);
+        let pixels = tile_size.to_pixels() as f64;
+        (
+            (self.x - tile.x as f64) * pixels,
+            (self.y - tile.y as f64) * pixels,
+        )
+    }
+}
+
+impl From<SlippyTileCoordinates> for FractionalTileCoordinates {
+    /// The north-west corner of the tile.
+    fn from(coords: SlippyTileCoordinates) -> Self {
+        FractionalTileCoordinates {
+            x: coords.x as f64,
+            y: coords.y as f64,
+        }
+    }
+}
+
 /// Real-world latitude/longitude coordinates.
 /// This format is for the user's convenicence - values get converted to SlippyTileCoordinates for the request.
 #[derive(Debug, Clone, Copy, PartialEq, Component)]
@@ -44,10 +125,51 @@ pub struct LatitudeLongitudeCoordinates {
 }
 
 impl LatitudeLongitudeCoordinates {
+    pub fn new(latitude: f64, longitude: f64) -> LatitudeLongitudeCoordinates {
+        LatitudeLongitudeCoordinates {
+            latitude,
+            longitude,
+        }
+    }
+
+    /// Checked version of [`LatitudeLongitudeCoordinates::new`], failing on NaN or infinite coordinates.
+    pub fn try_new(
+        latitude: f64,
+        longitude: f64,
+    ) -> Result<LatitudeLongitudeCoordinates, InvalidCoordinates> {
+        if !latitude.is_finite() {
+            return Err(InvalidCoordinates::Latitude(latitude));
+        }
+        if !longitude.is_finite() {
+            return Err(InvalidCoordinates::Longitude(longitude));
+        }
+        Ok(LatitudeLongitudeCoordinates::new(latitude, longitude))
+    }
+
+    /// The same point with its latitude clamped to the Web Mercator limits and its longitude within -180..180.
+    pub fn normalized(&self) -> LatitudeLongitudeCoordinates {
+        LatitudeLongitudeCoordinates {
+            latitude: clamp_latitude(self.latitude),
+            longitude: normalize_longitude(self.longitude),
+        }
+    }
+
     /// Get slippy tile coordinates based on a real-world lat/lon and zoom level.
     pub fn to_slippy_tile_coordinates(&self, zoom_level: ZoomLevel) -> SlippyTileCoordinates {
         SlippyTileCoordinates::from_latitude_longitude(self.latitude, self.longitude, zoom_level)
     }
+
+    /// Get fractional tile coordinates, keeping where the point falls within its tile.
+    pub fn to_fractional_tile_coordinates(
+        &self,
+        zoom_level: ZoomLevel,
+    ) -> FractionalTileCoordinates {
+        FractionalTileCoordinates::from_latitude_longitude(
+            self.latitude,
+            self.longitude,
+            zoom_level,
+        )
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Component)]
@@ -68,6 +190,15 @@ impl Coordinates {
         })
     }
 
+    /// Checked version of [`Coordinates::from_latitude_longitude`], failing on NaN or infinite coordinates.
+    pub fn try_from_latitude_longitude(
+        latitude: f64,
+        longitude: f64,
+    ) -> Result<Coordinates, InvalidCoordinates> {
+        LatitudeLongitudeCoordinates::try_new(latitude, longitude)
+            .map(Coordinates::LatitudeLongitude)
+    }
+
     pub fn get_slippy_tile_coordinates(&self, zoom_level: ZoomLevel) -> SlippyTileCoordinates {
         match &self {
             Coordinates::LatitudeLongitude(coords) => coords.to_slippy_tile_coordinates(zoom_level),
@@ -76,15 +207,414 @@ impl Coordinates {
     }
 }
 
+/// A rectangular range of slippy tiles at a given zoom level, in the given tiling scheme.
+///
+/// Columns wrap around the antimeridian: the range starts at column `x` and spans `width` columns eastward,
+/// continuing from column 0 past the last column.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SlippyTileRange {
+    /// Westernmost column.
+    pub x: u32,
+    /// Northernmost row.
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+    pub zoom_level: ZoomLevel,
+    pub tiling_scheme: TilingScheme,
+}
+
+impl SlippyTileRange {
+    /// Get the exact range of tiles covering a latitude/longitude bounding box.
+    ///
+    /// If `north_west` is east of `south_east`, the bounding box crosses the antimeridian.
+    /// Latitudes are clamped to the edges of the map (the Web Mercator limits for Web Mercator tiles).
+    pub fn from_bounds(
+        north_west: LatitudeLongitudeCoordinates,
+        south_east: LatitudeLongitudeCoordinates,
+        zoom_level: ZoomLevel,
+        tiling_scheme: TilingScheme,
+    ) -> SlippyTileRange {
+        let columns = tiling_scheme.columns(zoom_level) as i64;
+        let last_row = tiling_scheme.rows(zoom_level) as i64 - 1;
+        // Measure the east edge from the west edge, so longitudes outside -180..180 wrap like everywhere else.
+        let west = normalize_longitude(north_west.longitude);
+        let mut span = (south_east.longitude - north_west.longitude).rem_euclid(360.0);
+        if span == 0.0 && south_east.longitude != north_west.longitude {
+            // Distinct edges on the same meridian go all the way around.
+            span = 360.0;
+        }
+        let to_tile_x = |lon: f64| columns as f64 * (lon + 180.0) / 360.0;
+        let to_tile_y = |lat: f64| tiling_scheme.latitude_to_tile_y(lat, zoom_level);
+        let north = to_tile_y(north_west.latitude).min(to_tile_y(south_east.latitude));
+        let south = to_tile_y(north_west.latitude).max(to_tile_y(south_east.latitude));
+
+        // Tiles only touching the east/south edge of the bounding box are not part of it.
+        let min_x = (to_tile_x(west).floor() as i64).min(columns - 1);
+        let max_x = to_tile_x(west + span).ceil() as i64 - 1;
+        let min_y = (north.floor() as i64).min(last_row);
+        let max_y = (south.ceil() as i64 - 1).clamp(min_y, last_row);
+
+        // Columns past the antimeridian wrap around to the start of the row.
+        let width = (max_x.max(min_x) - min_x + 1).min(columns);
+        SlippyTileRange {
+            x: min_x as u32,
+            y: min_y as u32,
+            width: width as u32,
+            height: (max_y - min_y + 1) as u32,
+            zoom_level,
+            tiling_scheme,
+        }
+    }
+
+    /// Get the range of tiles between two corner coordinates, inclusive.
+    ///
+    /// Slippy tile corners are used as is, latitude/longitude corners are converted with [`SlippyTileRange::from_bounds`].
+    pub fn from_corners(
+        north_west: &Coordinates,
+        south_east: &Coordinates,
+        zoom_level: ZoomLevel,
+        tiling_scheme: TilingScheme,
+    ) -> SlippyTileRange {
+        match (north_west, south_east) {
+            (
+                Coordinates::LatitudeLongitude(north_west),
+                Coordinates::LatitudeLongitude(south_east),
+            ) => SlippyTileRange::from_bounds(*north_west, *south_east, zoom_level, tiling_scheme),
+            _ => {
+                let (columns, rows) = (
+                    tiling_scheme.columns(zoom_level),
+                    tiling_scheme.rows(zoom_level),
+                );
+                let north_west = tiling_scheme.slippy_tile_coordinates(north_west, zoom_level);
+                let south_east = tiling_scheme.slippy_tile_coordinates(south_east, zoom_level);
+                let (min_x, max_x) = (north_west.x.min(columns - 1), south_east.x.min(columns - 1));
+                let (min_y, max_y) = (
+                    north_west.y.min(south_east.y).min(rows - 1),
+                    north_west.y.max(south_east.y).min(rows - 1),
+                );
+                SlippyTileRange {
+                    x: min_x,
+                    y: min_y,
+                    // Crosses the antimeridian if the west corner is east of the east corner.
+                    width: (max_x + columns - min_x) % columns + 1,
+                    height: max_y - min_y + 1,
+                    zoom_level,
+                    tiling_scheme,
+                }
+            },
+        }
+    }
+
+    /// Get the range of tiles within `radius` tiles of `center`.
+    ///
+    /// Columns wrap around the antimeridian, rows stop at the top and bottom edges of the map.
+    pub fn around(
+        center: SlippyTileCoordinates,
+        radius: u32,
+        zoom_level: ZoomLevel,
+        tiling_scheme: TilingScheme,
+    ) -> SlippyTileRange {
+        let (columns, rows) = (
+            tiling_scheme.columns(zoom_level) as i64,
+            tiling_scheme.rows(zoom_level) as i64,
+        );
+        let (x, y, radius) = (
+            center.x as i64,
+            (center.y as i64).min(rows - 1),
+            radius as i64,
+        );
+        let min_y = (y - radius).max(0);
+        let max_y = (y + radius).min(rows - 1);
+        SlippyTileRange {
+            x: (x - radius).rem_euclid(columns) as u32,
+            y: min_y as u32,
+            width: (2 * radius + 1).min(columns) as u32,
+            height: (max_y - min_y + 1) as u32,
+            zoom_level,
+            tiling_scheme,
+        }
+    }
+
+    /// Number of tiles in the range.
+    pub fn len(&self) -> usize {
+        self.width as usize * self.height as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn contains(&self, coords: SlippyTileCoordinates) -> bool {
+        let columns = self.tiling_scheme.columns(self.zoom_level) as u64;
+        let column = (coords.x as u64 + columns - self.x as u64 % columns) % columns;
+        column < self.width as u64 && coords.y >= self.y && coords.y - self.y < self.height
+    }
+
+    /// Iterate over the tiles in the range, row by row from the north-west corner.
+    pub fn iter(&self) -> impl Iterator<Item = SlippyTileCoordinates> {
+        let range = *self;
+        let columns = range.tiling_scheme.columns(range.zoom_level) as u64;
+        (range.y..range.y + range.height).flat_map(move |y| {
+            (0..range.width as u64).map(move |column| SlippyTileCoordinates {
+                x: ((range.x as u64 + column) % columns) as u32,
+                y,
+            })
+        })
+    }
+}
+
+/// A latitude/longitude bounding box.
+///
+/// If `west` is greater than `east`, the bounding box crosses the antimeridian.
+/// Computed bounds keep `west` in -180..180 like [`normalize_longitude`], and `east` in -180..=180.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LatLonBounds {
+    pub north: f64,
+    pub south: f64,
+    pub west: f64,
+    pub east: f64,
+}
+
+impl LatLonBounds {
+    /// The whole world, from pole to pole.
+    pub const WORLD: LatLonBounds = LatLonBounds {
+        north: 90.0,
+        south: -90.0,
+        west: -180.0,
+        east: 180.0,
+    };
+
+    /// Get the bounding box between two corners. If `north_west` is east of `south_east`, it crosses the antimeridian.
+    pub fn from_corners(
+        north_west: LatitudeLongitudeCoordinates,
+        south_east: LatitudeLongitudeCoordinates,
+    ) -> LatLonBounds {
+        LatLonBounds {
+            north: north_west.latitude.max(south_east.latitude),
+            south: north_west.latitude.min(south_east.latitude),
+            west: north_west.longitude,
+            east: south_east.longitude,
+        }
+    }
+
+    /// Get the bounding box covered by a range of tiles.
+    pub fn from_tile_range(range: &SlippyTileRange) -> LatLonBounds {
+        if range.tiling_scheme == TilingScheme::Geographic {
+            let columns = range.tiling_scheme.columns(range.zoom_level);
+            let north_west = range.tiling_scheme.tile_bounds(
+                SlippyTileCoordinates {
+                    x: range.x,
+                    y: range.y,
+                },
+                range.zoom_level,
+            );
+            let south_east = range.tiling_scheme.tile_bounds(
+                SlippyTileCoordinates {
+                    x: (range.x + range.width - 1) % columns,
+                    y: range.y + range.height - 1,
+                },
+                range.zoom_level,
+            );
+            let bounds = LatLonBounds {
+                north: north_west.north,
+                south: south_east.south,
+                ..LatLonBounds::WORLD
+            };
+            return match range.width >= columns {
+                true => bounds,
+                false => LatLonBounds {
+                    west: north_west.west,
+                    east: south_east.east,
+                    ..bounds
+                },
+            };
+        }
+        let zoom = range.zoom_level.to_u8() as u32;
+        let tiles = 1u64 << zoom;
+        if range.width as u64 >= tiles {
+            return LatLonBounds {
+                north: tile_y_to_latitude(range.y, zoom),
+                south: tile_y_to_latitude(range.y + range.height, zoom),
+                ..LatLonBounds::WORLD
+            };
+        }
+        let east = tile_x_to_longitude(range.x + range.width, zoom);
+        LatLonBounds {
+            north: tile_y_to_latitude(range.y, zoom),
+            south: tile_y_to_latitude(range.y + range.height, zoom),
+            west: tile_x_to_longitude(range.x, zoom),
+            east: if east > 180.0 { east - 360.0 } else { east },
+        }
+    }
+
+    pub fn north_west(&self) -> LatitudeLongitudeCoordinates {
+        LatitudeLongitudeCoordinates {
+            latitude: self.north,
+            longitude: self.west,
+        }
+    }
+
+    pub fn south_east(&self) -> LatitudeLongitudeCoordinates {
+        LatitudeLongitudeCoordinates {
+            latitude: self.south,
+            longitude: self.east,
+        }
+    }
+
+    pub fn crosses_antimeridian(&self) -> bool {
+        self.west > self.east
+    }
+
+    /// Width of the bounding box, in degrees of longitude.
+    pub fn longitude_span(&self) -> f64 {
+        if self.crosses_antimeridian() {
+            self.east - self.west + 360.0
+        } else {
+            self.east - self.west
+        }
+    }
+
+    /// The point halfway between the edges of the bounding box.
+    pub fn center(&self) -> LatitudeLongitudeCoordinates {
+        LatitudeLongitudeCoordinates {
+            latitude: (self.north + self.south) / 2.0,
+            longitude: normalize_longitude(self.west + self.longitude_span() / 2.0),
+        }
+    }
+
+    /// Whether the point lies within the bounding box (edges included).
+    pub fn contains(&self, coords: LatitudeLongitudeCoordinates) -> bool {
+        let longitude = (coords.longitude - self.west).rem_euclid(360.0);
+        coords.latitude <= self.north
+            && coords.latitude >= self.south
+            && (longitude <= self.longitude_span() || coords.longitude == self.east)
+    }
+
+    /// The area covered by both bounding boxes, if any.
+    ///
+    /// Bounding boxes crossing the antimeridian can overlap on both sides of the world, only the largest overlap is
+    /// returned then.
+    pub fn intersection(&self, other: &LatLonBounds) -> Option<LatLonBounds> {
+        let (north, south) = (self.north.min(other.north), self.south.max(other.south));
+        if north < south {
+            return None;
+        }
+        // Longitudes relative to the west edge of this bounding box.
+        let span = self.longitude_span();
+        let other_span = other.longitude_span();
+        if span >= 360.0 {
+            return Some(other.with_longitudes(north, south, other.west, other_span));
+        }
+        let offset = (other.west - self.west).rem_euclid(360.0);
+        [offset, offset - 360.0]
+            .into_iter()
+            .map(|start| (start.max(0.0), (start + other_span).min(span)))
+            .filter(|(west, east)| west <= east)
+            .max_by(|a, b| (a.1 - a.0).total_cmp(&(b.1 - b.0)))
+            .map(|(west, east)| self.with_longitudes(north, south, self.west + west, east - west))
+    }
+
+    /// The smallest bounding box covering both bounding boxes.
+    pub fn union(&self, other: &LatLonBounds) -> LatLonBounds {
+        let (north, south) = (self.north.max(other.north), self.south.min(other.south));
+        // Either start from the west edge of this bounding box, or from the west edge of the other one.
+        let span = self.longitude_span();
+        let other_span = other.longitude_span();
+        let from_self = span.max((other.west - self.west).rem_euclid(360.0) + other_span);
+        let from_other = other_span.max((self.west - other.west).rem_euclid(360.0) + span);
+        if from_self <= from_other {
+            self.with_longitudes(north, south, self.west, from_self)
+        } else {
+            self.with_longitudes(north, south, other.west, from_other)
+        }
+    }
+
+    /// Grows the bounding box by `meters` on every side (shrinks it if negative).
+    ///
+    /// Latitudes stop at the poles, the bounding box spans the whole world once wide enough to go all the way around.
+    pub fn expand(&self, meters: f64) -> LatLonBounds {
+        let degrees = meters * DEGREES_PER_METER;
+        let north = (self.north + degrees).min(90.0);
+        let south = (self.south - degrees).max(-90.0);
+        // A meter is more degrees of longitude further from the equator.
+        let latitude = north.abs().max(south.abs()).min(MAX_LATITUDE);
+        let longitude_degrees = degrees / latitude.to_radians().cos();
+        let span = self.longitude_span() + 2.0 * longitude_degrees;
+        self.with_longitudes(
+            north,
+            south.min(north),
+            self.west - longitude_degrees,
+            span.max(0.0),
+        )
+    }
+
+    /// Get the range of Web Mercator tiles covering the bounding box at the given zoom level.
+    pub fn to_tile_range(&self, zoom_level: ZoomLevel) -> SlippyTileRange {
+        SlippyTileRange::from_bounds(
+            self.north_west(),
+            self.south_east(),
+            zoom_level,
+            TilingScheme::WebMercator,
+        )
+    }
+
+    /// Iterate over the tiles covering the bounding box at the given zoom level, row by row from the north-west corner.
+    pub fn tiles(&self, zoom_level: ZoomLevel) -> impl Iterator<Item = SlippyTileCoordinates> {
+        self.to_tile_range(zoom_level).iter()
+    }
+
+    fn with_longitudes(&self, north: f64, south: f64, west: f64, span: f64) -> LatLonBounds {
+        if span >= 360.0 {
+            return LatLonBounds {
+                north,
+                south,
+                ..LatLonBounds::WORLD
+            };
+        }
+        let west = normalize_longitude(west);
+        let east = west + span;
+        LatLonBounds {
+            north,
+            south,
+            west,
+            east: if east > 180.0 { east - 360.0 } else { east },
+        }
+    }
+}
+
+// Fractional tile y of a latitude, between 0 and the number of tiles.
+pub(crate) fn latitude_to_tile_y_fraction(lat: f64, zoom_level: ZoomLevel) -> f64 {
+    let tiles = max_tiles_in_dimension(zoom_level);
+    let lat_rad = lat.clamp(-MAX_LATITUDE, MAX_LATITUDE).to_radians();
+    (tiles * (1.0 - (lat_rad.tan() + (1.0 / lat_rad.cos())).ln() / PI) / 2.0).clamp(0.0, tiles)
+}
+
+/// Clamps a latitude to the Web Mercator limits (±[`MAX_LATITUDE`]).
+pub fn clamp_latitude(lat: f64) -> f64 {
+    lat.clamp(-MAX_LATITUDE, MAX_LATITUDE)
+}
+
+/// Wraps a longitude around the antimeridian, to -180..180 (180 itself becomes -180).
+///
+/// This is the convention used for every longitude the crate normalizes, except the east edge of a [`LatLonBounds`],
+/// which keeps 180 so the bounding box can end at the antimeridian.
+pub fn normalize_longitude(lon: f64) -> f64 {
+    (lon + 180.0).rem_euclid(360.0) - 180.0
+}
+
 // https://wiki.openstreetmap.org/wiki/Slippy_map_tilenames#Implementations
+// The latitude is clamped to the Web Mercator limits, so the tile row is always on the map.
 pub fn latitude_to_tile_y(lat: f64, zoom: u32) -> u32 {
-    let lat_rad = lat.to_radians();
-    ((1 << zoom) as f64 * (1.0 - (lat_rad.tan() + (1.0 / lat_rad.cos())).ln() / PI) / 2.0) as u32
+    let lat_rad = clamp_latitude(lat).to_radians();
+    let y = (1u64 << zoom) as f64 * (1.0 - (lat_rad.tan() + (1.0 / lat_rad.cos())).ln() / PI) / 2.0;
+    (y as u32).min((1 << zoom) - 1)
 }
 
 // https://wiki.openstreetmap.org/wiki/Slippy_map_tilenames#Implementations
+// The longitude wraps around the antimeridian.
 pub fn longitude_to_tile_x(lon: f64, zoom: u32) -> u32 {
-    ((1 << zoom) as f64 * (lon + 180.0) / 360.0) as u32
+    let x = (1u64 << zoom) as f64 * (normalize_longitude(lon) + 180.0) / 360.0;
+    (x as u32).min((1 << zoom) - 1)
 }
 
 // https://wiki.openstreetmap.org/wiki/Slippy_map_tilenames#Implementations
@@ -118,17 +648,8 @@ pub fn world_pixel_to_world_coords(
     zoom_level: ZoomLevel,
 ) -> LatitudeLongitudeCoordinates {
     // Flip Y axis because Bevy has (0,0) at the bottom left, but the calculation is for (0,0) at the top left.
-    // TODO: Cache this max pixels value?
-    let max_pixels = max_pixels_in_dimension(zoom_level, tile_size);
-    let y_pixel = max_pixels - y_pixel;
-    let (longitude, latitude) =
-        googleprojection::Mercator::with_size(tile_size.to_pixels() as usize)
-            .from_pixel_to_ll(&(x_pixel, y_pixel), zoom_level.to_u8().into())
-            .unwrap_or_default();
-    LatitudeLongitudeCoordinates {
-        latitude,
-        longitude,
-    }
+    let projection = WebMercatorProjection::new(tile_size, zoom_level);
+    projection.to_latitude_longitude(x_pixel, projection.world_pixels() - y_pixel)
 }
 
 // Given world coordinates, return the x and y pixel position in the world.
@@ -137,15 +658,8 @@ pub fn world_coords_to_world_pixel(
     tile_size: TileSize,
     zoom_level: ZoomLevel,
 ) -> (f64, f64) {
-    let (x, y) = googleprojection::Mercator::with_size(tile_size.to_pixels() as usize)
-        .from_ll_to_subpixel(
-            &(coords.longitude, coords.latitude),
-            zoom_level.to_u8().into(),
-        )
-        .unwrap_or_default();
+    let projection = WebMercatorProjection::new(tile_size, zoom_level);
+    let (x, y) = projection.to_pixel(coords);
     // Flip Y axis because Bevy has (0,0) at the bottom left, but the calculation is for (0,0) at the top left.
-    // TODO: Cache this max pixels value?
-    let max_pixels = max_pixels_in_dimension(zoom_level, tile_size);
-    let y = max_pixels - y;
-    (x, y)
+    (x, projection.world_pixels() - y)
 }
@@ -1,7 +1,4 @@
-use crate::{
-    world_coords_to_world_pixel, LatitudeLongitudeCoordinates, SlippyTileDownloadedMessage,
-    SlippyTilesSettings,
-};
+use crate::{LatitudeLongitudeCoordinates, SlippyTileDownloadedMessage, SlippyTilesSettings};
 use bevy::prelude::*;
 
 /// Component to mark entities as map tiles
@@ -20,27 +17,36 @@ pub fn display_tiles(
         return;
     }
 
+    let tiling_scheme = settings.tiling_scheme;
     for message in tile_messages.read() {
         // Convert reference coordinates to pixel coordinates
         let reference_point = LatitudeLongitudeCoordinates {
             latitude: settings.reference_latitude,
             longitude: settings.reference_longitude,
         };
-        let (ref_x, ref_y) =
-            world_coords_to_world_pixel(&reference_point, message.tile_size, message.zoom_level);
+        let (ref_x, ref_y) = tiling_scheme.world_coords_to_world_pixel(
+            &reference_point,
+            message.tile_size,
+            message.zoom_level,
+        );
 
         // Convert tile coordinates to pixel coordinates
         let current_coords = match message.coordinates {
             crate::Coordinates::LatitudeLongitude(coords) => coords,
             crate::Coordinates::SlippyTile(coords) => {
-                coords.to_latitude_longitude(message.zoom_level)
+                tiling_scheme.tile_to_latitude_longitude(coords, message.zoom_level)
             },
         };
-        let (tile_x, tile_y) =
-            world_coords_to_world_pixel(&current_coords, message.tile_size, message.zoom_level);
+        let (tile_x, tile_y) = tiling_scheme.world_coords_to_world_pixel(
+            &current_coords,
+            message.tile_size,
+            message.zoom_level,
+        );
 
-        // Calculate offset from reference point
-        let mut transform_x = (tile_x - ref_x) as f32;
+        // Calculate offset from reference point, placing the tile on the side of the antimeridian closest to it
+        let (world_pixels, _) = tiling_scheme.world_pixels(message.tile_size, message.zoom_level);
+        let tile_pixels = message.tile_size.to_pixels() as f64;
+        let mut transform_x = wrap_pixel_offset(tile_x - ref_x, tile_pixels, world_pixels) as f32;
         let mut transform_y = (tile_y - ref_y) as f32;
 
         // Apply optional transform offset
@@ -57,3 +63,11 @@ pub fn display_tiles(
         ));
     }
 }
+
+/// Wraps a horizontal pixel offset around the world so the tile's center ends up within half a world of the reference.
+pub(crate) fn wrap_pixel_offset(offset: f64, tile_pixels: f64, world_pixels: f64) -> f64 {
+    let center = offset + tile_pixels / 2.0;
+    center
+        - world_pixels * ((center + world_pixels / 2.0) / world_pixels).floor()
+        - tile_pixels / 2.0
+}
@@ -1,10 +1,17 @@
-use std::path::{Path, PathBuf};
+use std::{
+    path::{Path, PathBuf},
+    time::{Instant, SystemTime},
+};
 
 use bevy::{ecs::message::Message, prelude::Resource, tasks::Task};
-use bevy_platform::collections::HashMap;
+use bevy_platform::collections::{HashMap, HashSet};
 
-use crate::coordinates::{Coordinates, SlippyTileCoordinates};
-use crate::types::{DownloadStatus, TileSize, ZoomLevel};
+use crate::coordinates::{Coordinates, SlippyTileCoordinates, SlippyTileRange};
+use crate::region::TileRegion;
+use crate::stats::TileFetchStats;
+use crate::tile::TileId;
+use crate::tiling::TilingScheme;
+use crate::types::{DownloadStatus, TileDownloadError, TileSize, ZoomLevel};
 
 // Unique representation of a slippy tile download task.
 #[derive(Eq, PartialEq, Hash, Clone)]
@@ -14,6 +21,20 @@ pub struct SlippyTileDownloadTaskKey {
     pub tile_size: TileSize,
 }
 
+impl SlippyTileDownloadTaskKey {
+    pub fn new(tile_id: TileId, tile_size: TileSize) -> SlippyTileDownloadTaskKey {
+        SlippyTileDownloadTaskKey {
+            slippy_tile_coordinates: tile_id.slippy_tile_coordinates(),
+            zoom_level: tile_id.zoom_level,
+            tile_size,
+        }
+    }
+
+    pub fn tile_id(&self) -> TileId {
+        TileId::from_slippy_tile_coordinates(self.slippy_tile_coordinates, self.zoom_level)
+    }
+}
+
 /// HashMap that keeps track of the slippy tiles that have been downloaded.
 #[derive(Resource)]
 pub struct SlippyTileDownloadStatus(pub HashMap<SlippyTileDownloadTaskKey, TileDownloadStatus>);
@@ -58,10 +79,16 @@ impl SlippyTileDownloadStatus {
             TileDownloadStatus {
                 path: Path::new(&filename).to_path_buf(),
                 load_status: download_status,
+                since: Instant::now(),
             },
         );
     }
 
+    pub fn get(&self, tile_id: TileId, tile_size: TileSize) -> Option<&TileDownloadStatus> {
+        self.0
+            .get(&SlippyTileDownloadTaskKey::new(tile_id, tile_size))
+    }
+
     pub fn contains_key(&self, x: u32, y: u32, zoom_level: ZoomLevel, tile_size: TileSize) -> bool {
         self.contains_key_with_coords(SlippyTileCoordinates { x, y }, zoom_level, tile_size)
     }
@@ -90,13 +117,20 @@ impl Default for SlippyTileDownloadStatus {
 pub struct TileDownloadStatus {
     pub path: PathBuf,
     pub load_status: DownloadStatus,
+    /// When the tile entered its current status - for downloaded tiles, when they were downloaded (even by an earlier run).
+    pub since: Instant,
 }
 
 /// A wrapper type that represents the results of the async task used to download tiles.
-/// Contains the path of the tile downloaded.
+/// Contains the path of the tile downloaded, and the error if it could not be retrieved.
 #[derive(Clone)]
 pub struct SlippyTileDownloadTaskResult {
     pub path: PathBuf,
+    pub result: Result<(), TileDownloadError>,
+    /// Network statistics of the download, `None` if the tile was loaded from disk.
+    pub fetch_stats: Option<TileFetchStats>,
+    /// When the tile was downloaded, tiles loaded from disk keep the time of their original download.
+    pub downloaded_at: Option<SystemTime>,
 }
 
 /// HashMap of all tiles currently being downloaded.
@@ -145,6 +179,56 @@ impl Default for SlippyTileDownloadTasks {
     }
 }
 
+/// In-memory index of the slippy tiles found in the tiles directory.
+/// Only built (in the background, at startup) when [`SlippyTilesSettings::cache_index`](crate::SlippyTilesSettings) is enabled.
+/// Until it is ready, cache lookups fall back to checking the disk on the IO task pool.
+#[derive(Resource)]
+pub struct SlippyTileCacheIndex {
+    pub(crate) keys: HashSet<SlippyTileDownloadTaskKey>,
+    pub(crate) task: Option<Task<HashSet<SlippyTileDownloadTaskKey>>>,
+    pub(crate) ready: bool,
+}
+
+impl SlippyTileCacheIndex {
+    pub fn new() -> SlippyTileCacheIndex {
+        SlippyTileCacheIndex {
+            keys: HashSet::new(),
+            task: None,
+            ready: false,
+        }
+    }
+
+    /// Whether the tiles directory has been fully scanned.
+    pub fn is_ready(&self) -> bool {
+        self.ready
+    }
+
+    pub fn contains(&self, key: &SlippyTileDownloadTaskKey) -> bool {
+        self.keys.contains(key)
+    }
+
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    /// Records a tile that was written to the tiles directory. Ignored if the index is not in use.
+    pub(crate) fn insert(&mut self, key: SlippyTileDownloadTaskKey) {
+        if self.ready || self.task.is_some() {
+            self.keys.insert(key);
+        }
+    }
+}
+
+impl Default for SlippyTileCacheIndex {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 /// Users send these messages to request slippy tile downloads.
 #[derive(Debug, Message)]
 pub struct DownloadSlippyTilesMessage {
@@ -162,6 +246,75 @@ impl DownloadSlippyTilesMessage {
         self.coordinates
             .get_slippy_tile_coordinates(self.zoom_level)
     }
+
+    /// The center Web Mercator tile and its surrounding tiles within `radius`.
+    pub fn get_slippy_tile_range(&self) -> SlippyTileRange {
+        SlippyTileRange::around(
+            self.get_slippy_tile_coordinates(),
+            self.radius.0 as u32,
+            self.zoom_level,
+            TilingScheme::WebMercator,
+        )
+    }
+
+    /// The center tile and its surrounding tiles within `radius`, in the given tiling scheme.
+    pub fn tiles(&self, tiling_scheme: TilingScheme) -> Vec<SlippyTileCoordinates> {
+        tiling_scheme.tiles_around(
+            tiling_scheme.slippy_tile_coordinates(&self.coordinates, self.zoom_level),
+            self.radius.0 as u32,
+            self.zoom_level,
+        )
+    }
+}
+
+/// Send this message to request every slippy tile covering a bounding box.
+#[derive(Debug, Message)]
+pub struct DownloadSlippyTileBoundsMessage {
+    pub tile_size: TileSize,
+    pub zoom_level: ZoomLevel,
+    /// North-west corner of the bounding box.
+    pub north_west: Coordinates,
+    /// South-east corner of the bounding box - the box crosses the antimeridian if this is west of `north_west`.
+    pub south_east: Coordinates,
+    /// If set to false, will force download of new tiles from the endpoint regardless of previous requests and tiles already on disk.
+    pub use_cache: bool,
+}
+
+impl DownloadSlippyTileBoundsMessage {
+    /// The Web Mercator tiles covering the bounding box.
+    pub fn get_slippy_tile_range(&self) -> SlippyTileRange {
+        SlippyTileRange::from_corners(
+            &self.north_west,
+            &self.south_east,
+            self.zoom_level,
+            TilingScheme::WebMercator,
+        )
+    }
+
+    /// Every tile covering the bounding box, in the given tiling scheme.
+    pub fn tiles(&self, tiling_scheme: TilingScheme) -> Vec<SlippyTileCoordinates> {
+        tiling_scheme.tiles_between(&self.north_west, &self.south_east, self.zoom_level)
+    }
+}
+
+/// Send this message to request every slippy tile intersecting a [`TileRegion`] (polygon or route corridor),
+/// at every zoom level from `min_zoom_level` to `max_zoom_level`.
+#[derive(Debug, Message)]
+pub struct DownloadSlippyTileRegionMessage {
+    pub tile_size: TileSize,
+    pub min_zoom_level: ZoomLevel,
+    pub max_zoom_level: ZoomLevel,
+    pub region: TileRegion,
+    /// If set to false, will force download of new tiles from the endpoint regardless of previous requests and tiles already on disk.
+    pub use_cache: bool,
+}
+
+impl DownloadSlippyTileRegionMessage {
+    /// The requested zoom levels, from the lowest to the highest.
+    pub fn zoom_levels(&self) -> impl Iterator<Item = ZoomLevel> {
+        (self.min_zoom_level.to_u8()..=self.max_zoom_level.to_u8())
+            .filter_map(|zoom| ZoomLevel::try_from(zoom).ok())
+    }
 }
 
 /// This is deprecated. See [`DownloadSlippyTilesMessage`](crate::download::DownloadSlippyTilesMessage)
@@ -188,6 +341,26 @@ impl SlippyTileDownloadedMessage {
     }
 }
 
+/// The library will generate these messages when a requested slippy tile could not be retrieved.
+#[derive(Debug, Message)]
+pub struct SlippyTileDownloadFailedMessage {
+    /// The [`TileSize`] used for this slippy tile.
+    pub tile_size: TileSize,
+    /// The [`ZoomLevel`] used for this slippy tile.
+    pub zoom_level: ZoomLevel,
+    /// The [`Coordinates`] used for this slippy tile.
+    pub coordinates: Coordinates,
+    /// Why the slippy tile could not be retrieved.
+    pub error: TileDownloadError,
+}
+
+impl SlippyTileDownloadFailedMessage {
+    pub fn get_slippy_tile_coordinates(&self) -> SlippyTileCoordinates {
+        self.coordinates
+            .get_slippy_tile_coordinates(self.zoom_level)
+    }
+}
+
 /// This is deprecated. See [`SlippyTileDownloadedMessage`](crate::download::SlippyTileDownloadedMessage)
 #[deprecated(since = "0.10.1", note = "Renamed to `SlippyTileDownloadedMessage`.")]
 pub type SlippyTileDownloadedEvent = SlippyTileDownloadedMessage;
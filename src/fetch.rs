@@ -0,0 +1,281 @@
+use async_io::Timer;
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use bevy::{
+    prelude::Resource,
+    tasks::{futures_lite::future, BoxedFuture},
+};
+use bevy_platform::collections::HashMap;
+use std::{
+    future::Future,
+    io::Read as _,
+    sync::{Arc, Mutex, OnceLock, PoisonError},
+    time::{Duration, Instant},
+};
+
+use crate::{ProxySettings, TileDownloadError};
+
+/// A single tile request, handed to the [`TileFetcher`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct TileRequest {
+    pub url: String,
+    pub headers: Vec<(String, String)>,
+    /// Maximum time to wait for the tile server to respond.
+    pub connect_timeout: Option<Duration>,
+    /// Maximum time for the whole request, including the response body.
+    pub request_timeout: Option<Duration>,
+    /// Proxy to send the request through, if any (`no_proxy` has already been applied).
+    pub proxy: Option<ProxySettings>,
+}
+
+/// The response to a [`TileRequest`], returned by the [`TileFetcher`] whatever the HTTP status.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TileResponse {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub bytes: Vec<u8>,
+}
+
+impl TileResponse {
+    /// Returns the value of the first header matching `name` (case-insensitive).
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// HTTP client used to download tiles.
+///
+/// Implement this to reuse your own HTTP client (connection pool, TLS configuration, middleware) or to inject a test double,
+/// then insert it as the [`SlippyTileFetcher`] resource. Non-200 responses should be returned as a [`TileResponse`],
+/// `Err` is reserved for requests that got no response at all.
+pub trait TileFetcher: Send + Sync + 'static {
+    fn fetch(
+        &self,
+        request: TileRequest,
+    ) -> BoxedFuture<'static, Result<TileResponse, TileDownloadError>>;
+}
+
+/// The [`TileFetcher`] used for tile downloads - [`UreqFetcher`] unless another one is inserted before adding the
+/// [`SlippyTilesPlugin`](crate::SlippyTilesPlugin).
+#[derive(Resource, Clone)]
+pub struct SlippyTileFetcher(pub Arc<dyn TileFetcher>);
+
+impl SlippyTileFetcher {
+    pub fn new(fetcher: impl TileFetcher) -> SlippyTileFetcher {
+        SlippyTileFetcher(Arc::new(fetcher))
+    }
+}
+
+impl Default for SlippyTileFetcher {
+    fn default() -> Self {
+        Self::new(UreqFetcher)
+    }
+}
+
+/// Hook rewriting tile requests just before they are sent, to sign URLs or attach short-lived credentials.
+///
+/// Insert it as the [`SlippyTileRequestHook`] resource. It runs on the download task, for every attempt.
+pub trait TileRequestHook: Send + Sync + 'static {
+    /// Rewrites the URL and headers of a request. An error fails the attempt, which is retried like any other failure.
+    fn prepare(
+        &self,
+        request: TileRequest,
+    ) -> BoxedFuture<'static, Result<TileRequest, TileDownloadError>>;
+
+    /// Called when the tile server responds 401 Unauthorized to a prepared request.
+    /// Return true once credentials are refreshed to have the request prepared and sent once more.
+    fn refresh(&self, _response: &TileResponse) -> BoxedFuture<'static, bool> {
+        Box::pin(async { false })
+    }
+}
+
+/// The [`TileRequestHook`] applied to tile requests, if any.
+#[derive(Resource, Clone, Default)]
+pub struct SlippyTileRequestHook(pub Option<Arc<dyn TileRequestHook>>);
+
+impl SlippyTileRequestHook {
+    pub fn new(hook: impl TileRequestHook) -> SlippyTileRequestHook {
+        SlippyTileRequestHook(Some(Arc::new(hook)))
+    }
+}
+
+/// The default [`TileFetcher`], backed by a [`ureq`] agent (the HTTP client ehttp uses natively).
+///
+/// Requests run on the shared thread pool for blocking work. The timeouts are applied to the sockets, so a stalled
+/// request gives its thread back once they expire, even if nobody is waiting on it anymore.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct UreqFetcher;
+
+impl TileFetcher for UreqFetcher {
+    fn fetch(
+        &self,
+        request: TileRequest,
+    ) -> BoxedFuture<'static, Result<TileResponse, TileDownloadError>> {
+        Box::pin(async move { fetch_with_timeouts(&request).await })
+    }
+}
+
+type Agents = HashMap<(Option<String>, Option<Duration>), ureq::Agent>;
+
+/// Returns a ureq agent sending requests through `proxy` (if any), agents are shared so connections get reused.
+fn agent(
+    proxy: Option<&ProxySettings>,
+    connect_timeout: Option<Duration>,
+) -> Result<ureq::Agent, TileDownloadError> {
+    static AGENTS: OnceLock<Mutex<Agents>> = OnceLock::new();
+
+    let proxy_url = proxy.map(ProxySettings::url_with_credentials);
+    let mut agents = AGENTS
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner);
+    if let Some(agent) = agents.get(&(proxy_url.clone(), connect_timeout)) {
+        return Ok(agent.clone());
+    }
+
+    let mut builder = ureq::AgentBuilder::new();
+    if let Some(proxy_url) = &proxy_url {
+        let ureq_proxy = ureq::Proxy::new(proxy_url)
+            .map_err(|e| TileDownloadError::Network(format!("Invalid proxy: {}", e)))?;
+        builder = builder.proxy(ureq_proxy);
+    }
+    if let Some(connect_timeout) = connect_timeout {
+        // Waiting on the tile server to respond is a socket read.
+        builder = builder
+            .timeout_connect(connect_timeout)
+            .timeout_read(connect_timeout);
+    }
+    let agent = builder.build();
+    agents.insert((proxy_url, connect_timeout), agent.clone());
+    Ok(agent)
+}
+
+/// Performs an HTTP GET request with ureq, through `request.proxy` if any.
+///
+/// Fails with [`TileDownloadError::ConnectTimeout`] if the tile server can't be reached or stops responding for
+/// `connect_timeout`, and with [`TileDownloadError::RequestTimeout`] if the whole response doesn't arrive within
+/// `request_timeout`. Socket reads are limited by `connect_timeout`, so dropping the returned future doesn't leave a
+/// thread blocked on a stalled connection for longer than that.
+pub(crate) async fn fetch_with_timeouts(
+    request: &TileRequest,
+) -> Result<TileResponse, TileDownloadError> {
+    let agent = agent(request.proxy.as_ref(), request.connect_timeout)?;
+    let mut ureq_request = agent.get(&request.url);
+    for (key, value) in &request.headers {
+        ureq_request = ureq_request.set(key, value);
+    }
+    // ureq ignores the read timeout once a request has a deadline, so with a connect timeout the request timeout is
+    // checked between reads instead (each read is limited by the connect timeout).
+    if let (None, Some(request_timeout)) = (request.connect_timeout, request.request_timeout) {
+        ureq_request = ureq_request.timeout(request_timeout);
+    }
+    // ureq only authenticates the CONNECT tunnel used for https, plain http requests are sent to the proxy directly.
+    if let Some(proxy) = &request.proxy {
+        if let (Some(username), false) = (&proxy.username, request.url.starts_with("https://")) {
+            let credentials = format!("{}:{}", username, proxy.password.as_deref().unwrap_or(""));
+            ureq_request = ureq_request.set(
+                "Proxy-Authorization",
+                &format!("Basic {}", STANDARD.encode(credentials)),
+            );
+        }
+    }
+
+    // ureq is blocking, run it on the shared thread pool for blocking work.
+    let request_timeout = request.request_timeout;
+    let response = blocking::unblock(move || {
+        let started = Instant::now();
+        let timed_out = || request_timeout.is_some_and(|timeout| started.elapsed() >= timeout);
+        read_ureq_response(ureq_request.call(), timed_out)
+    });
+    with_timeout(response, request_timeout)
+        .await
+        .unwrap_or(Err(TileDownloadError::RequestTimeout))
+}
+
+/// Reads a whole ureq response, `request_timed_out` tells whether the request timeout has elapsed.
+fn read_ureq_response(
+    result: Result<ureq::Response, ureq::Error>,
+    request_timed_out: impl Fn() -> bool,
+) -> Result<TileResponse, TileDownloadError> {
+    let timeout_error = || match request_timed_out() {
+        true => TileDownloadError::RequestTimeout,
+        false => TileDownloadError::ConnectTimeout,
+    };
+    let response = match result {
+        Ok(response) | Err(ureq::Error::Status(_, response)) => response,
+        Err(ureq::Error::Transport(e)) => {
+            let timed_out = std::error::Error::source(&e)
+                .and_then(|source| source.downcast_ref::<std::io::Error>())
+                .is_some_and(is_timeout);
+            return Err(if timed_out {
+                timeout_error()
+            } else {
+                TileDownloadError::Network(e.to_string())
+            });
+        },
+    };
+    if request_timed_out() {
+        return Err(TileDownloadError::RequestTimeout);
+    }
+
+    let status = response.status();
+    let headers = response
+        .headers_names()
+        .into_iter()
+        .filter_map(|name| {
+            let value = response.header(&name)?.to_owned();
+            Some((name, value))
+        })
+        .collect();
+    let mut reader = response.into_reader();
+    let mut bytes = Vec::new();
+    let mut chunk = [0; 8192];
+    loop {
+        match reader.read(&mut chunk) {
+            Ok(0) => break,
+            Ok(read) => bytes.extend_from_slice(&chunk[..read]),
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {},
+            Err(e) if is_timeout(&e) => return Err(timeout_error()),
+            Err(e) => {
+                return Err(TileDownloadError::Network(format!(
+                    "Failed to read response body: {}",
+                    e
+                )))
+            },
+        }
+        if request_timed_out() {
+            return Err(TileDownloadError::RequestTimeout);
+        }
+    }
+    Ok(TileResponse {
+        status,
+        headers,
+        bytes,
+    })
+}
+
+fn is_timeout(e: &std::io::Error) -> bool {
+    matches!(
+        e.kind(),
+        std::io::ErrorKind::TimedOut | std::io::ErrorKind::WouldBlock
+    )
+}
+
+/// Runs `fut` to completion, or gives up with `None` once `timeout` has elapsed.
+pub(crate) async fn with_timeout<T>(
+    fut: impl Future<Output = T>,
+    timeout: Option<Duration>,
+) -> Option<T> {
+    match timeout {
+        Some(timeout) => {
+            future::or(async { Some(fut.await) }, async {
+                Timer::after(timeout).await;
+                None
+            })
+            .await
+        },
+        None => Some(fut.await),
+    }
+}
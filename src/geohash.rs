@@ -0,0 +1,118 @@
+use std::{fmt, str::FromStr};
+
+use crate::{
+    coordinates::{LatLonBounds, LatitudeLongitudeCoordinates},
+    types::ParseCoordinatesError,
+};
+
+const GEOHASH_ALPHABET: &[u8; 32] = b"0123456789bcdefghjkmnpqrstuvwxyz";
+
+/// A geohash (<https://en.wikipedia.org/wiki/Geohash>), identifying a cell of the latitude/longitude grid.
+///
+/// Each character divides the cell into 32: 5 characters are about 5km wide, 8 characters about 40m and 12 characters
+/// a few centimeters.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Geohash(String);
+
+impl Geohash {
+    /// The geohash of the `length` characters cell containing a lat/lon.
+    pub fn encode(coords: &LatitudeLongitudeCoordinates, length: usize) -> Geohash {
+        let (mut latitude, mut longitude) = ((-90.0, 90.0), (-180.0, 180.0));
+        let mut geohash = String::with_capacity(length);
+        let mut even_bit = true;
+        for _ in 0..length {
+            let mut index = 0;
+            for _ in 0..5 {
+                // Bits alternate between longitude and latitude, starting with longitude.
+                let (range, value): (&mut (f64, f64), f64) = if even_bit {
+                    (&mut longitude, coords.longitude)
+                } else {
+                    (&mut latitude, coords.latitude)
+                };
+                let middle = (range.0 + range.1) / 2.0;
+                index <<= 1;
+                if value >= middle {
+                    index |= 1;
+                    range.0 = middle;
+                } else {
+                    range.1 = middle;
+                }
+                even_bit = !even_bit;
+            }
+            geohash.push(GEOHASH_ALPHABET[index] as char);
+        }
+        Geohash(geohash)
+    }
+
+    /// The geohash characters.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// The latitude/longitude bounding box of the cell.
+    pub fn bounds(&self) -> LatLonBounds {
+        let (mut latitude, mut longitude) = ((-90.0, 90.0), (-180.0, 180.0));
+        let mut even_bit = true;
+        for character in self.0.bytes() {
+            let index = GEOHASH_ALPHABET
+                .iter()
+                .position(|c| *c == character)
+                .unwrap_or_default();
+            for bit in (0..5).rev() {
+                let range: &mut (f64, f64) = if even_bit {
+                    &mut longitude
+                } else {
+                    &mut latitude
+                };
+                let middle = (range.0 + range.1) / 2.0;
+                if index >> bit & 1 == 1 {
+                    range.0 = middle;
+                } else {
+                    range.1 = middle;
+                }
+                even_bit = !even_bit;
+            }
+        }
+        LatLonBounds {
+            north: latitude.1,
+            south: latitude.0,
+            west: longitude.0,
+            east: longitude.1,
+        }
+    }
+
+    /// The center of the cell.
+    pub fn to_latitude_longitude(&self) -> LatitudeLongitudeCoordinates {
+        let bounds = self.bounds();
+        LatitudeLongitudeCoordinates {
+            latitude: (bounds.north + bounds.south) / 2.0,
+            longitude: (bounds.west + bounds.east) / 2.0,
+        }
+    }
+}
+
+impl fmt::Display for Geohash {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl FromStr for Geohash {
+    type Err = ParseCoordinatesError;
+
+    /// Parses a geohash, ignoring case.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let geohash = s.trim().to_ascii_lowercase();
+        if geohash.is_empty() || !geohash.bytes().all(|c| GEOHASH_ALPHABET.contains(&c)) {
+            return Err(ParseCoordinatesError::Format(s.into()));
+        }
+        Ok(Geohash(geohash))
+    }
+}
+
+impl LatitudeLongitudeCoordinates {
+    /// The geohash of the `length` characters cell containing this point.
+    pub fn to_geohash(&self, length: usize) -> Geohash {
+        Geohash::encode(self, length)
+    }
+}
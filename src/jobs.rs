@@ -0,0 +1,922 @@
+use bevy::{
+    asset::{
+        io::{AssetReaderError, AssetSourceId},
+        AssetServer, AsyncWriteExt as _,
+    },
+    ecs::message::Message,
+    prelude::{debug, warn, MessageReader, MessageWriter, Res, ResMut, Resource},
+    tasks::{
+        futures_lite::{future, StreamExt as _},
+        IoTaskPool, Task,
+    },
+};
+use bevy_platform::collections::HashMap;
+use std::{
+    collections::{BTreeSet, VecDeque},
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+use crate::{
+    region::TileRows,
+    systems::{self, endpoint_host, DownloadRateLimiter, FetchSettings},
+    DownloadStatus, FileExists, LatitudeLongitudeCoordinates, SlippyTileCacheIndex,
+    SlippyTileDownloadStatus, SlippyTileDownloadTaskKey, SlippyTileDownloadTaskResult,
+    SlippyTileFetcher, SlippyTileRequestHook, SlippyTileStats, SlippyTileValidator,
+    SlippyTilesSettings, TileDownloadError, TileRegion, TileSize, TilingScheme, ZoomLevel,
+    OSM_MAX_BULK_ZOOM_LEVEL,
+};
+
+/// Maximum number of tiles of a job being checked against the cache or waiting on a download slot.
+const LOOKAHEAD: usize = 64;
+/// Minimum time between two progress messages of a job, unless its state changes.
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(250);
+/// Minimum time between two saves of a job's progress, unless its state changes.
+const PERSIST_INTERVAL: Duration = Duration::from_secs(5);
+/// Directory (within the tiles directory) where job progress is saved.
+const JOBS_DIRECTORY: &str = "jobs/";
+
+/// A bulk download of every tile intersecting a region, at every zoom level from `min_zoom_level` to `max_zoom_level`.
+///
+/// Jobs are meant for offline use: tiles are stored in the tiles directory without being tracked in
+/// [`SlippyTileDownloadStatus`](crate::SlippyTileDownloadStatus) or announced with
+/// [`SlippyTileDownloadedMessage`](crate::SlippyTileDownloadedMessage)s.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SlippyTileJob {
+    /// Unique name of the job, used to control it and to save its progress.
+    pub name: String,
+    pub tile_size: TileSize,
+    pub min_zoom_level: ZoomLevel,
+    pub max_zoom_level: ZoomLevel,
+    pub region: TileRegion,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SlippyTileJobState {
+    Running,
+    Paused,
+    Completed,
+    Cancelled,
+    /// The job would break the OpenStreetMap tile usage policy, see [`OSM_MAX_BULK_ZOOM_LEVEL`] - checked whenever the job
+    /// runs, including once resumed or restored.
+    Refused,
+}
+
+/// Progress of a [`SlippyTileJob`] - `done` includes tiles that were already cached, tiles being downloaded for a
+/// regular tile request count towards `done` or `failed` once that download completes.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SlippyTileJobProgress {
+    pub name: String,
+    pub state: SlippyTileJobState,
+    pub total: u64,
+    pub done: u64,
+    pub failed: u64,
+    pub bytes: u64,
+}
+
+/// Send this message to start, pause, resume or cancel a [`SlippyTileJob`].
+#[derive(Debug, Message)]
+pub enum SlippyTileJobMessage {
+    Start(SlippyTileJob),
+    Pause(String),
+    Resume(String),
+    Cancel(String),
+}
+
+/// The library will generate these messages as jobs progress, at most every 250ms per job unless its state changes.
+#[derive(Debug, Message)]
+pub struct SlippyTileJobProgressMessage(pub SlippyTileJobProgress);
+
+/// Every bulk download job, started with [`SlippyTileJobMessage::Start`] or restored from a previous run.
+#[derive(Resource, Default)]
+pub struct SlippyTileJobs {
+    jobs: HashMap<String, TileJob>,
+    restore: Option<Task<Vec<PersistedJob>>>,
+}
+
+impl SlippyTileJobs {
+    pub fn new() -> SlippyTileJobs {
+        SlippyTileJobs::default()
+    }
+
+    pub fn progress(&self, name: &str) -> Option<&SlippyTileJobProgress> {
+        self.jobs.get(name).map(|job| &job.progress)
+    }
+
+    /// Abandons the job download of a stalled tile, failing it. Returns false if no job was downloading it.
+    pub(crate) fn fail_stalled_download(&mut self, key: &SlippyTileDownloadTaskKey) -> bool {
+        for job in self.jobs.values_mut() {
+            let Some(index) = job
+                .downloads
+                .iter()
+                .position(|(_, downloading, _)| downloading == key)
+            else {
+                continue;
+            };
+            // Dropping the task cancels it, releasing its download slot.
+            let (position, ..) = job.downloads.swap_remove(index);
+            warn!(
+                "Slippy tile job {} failed to download tile {}/{}/{}: {}",
+                job.job.name,
+                key.zoom_level.to_u8(),
+                key.slippy_tile_coordinates.x,
+                key.slippy_tile_coordinates.y,
+                TileDownloadError::Stalled
+            );
+            job.finish(position, false);
+            return true;
+        }
+        false
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &SlippyTileJobProgress> {
+        self.jobs.values().map(|job| &job.progress)
+    }
+}
+
+/// The persisted state of a job - enough to resume it without downloading or counting any tile twice.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct PersistedJob {
+    pub(crate) job: SlippyTileJob,
+    /// The tiling scheme the job's tiles are numbered in.
+    pub(crate) tiling_scheme: TilingScheme,
+    pub(crate) state: SlippyTileJobState,
+    /// Every tile before this position is finished.
+    pub(crate) resume_from: u64,
+    /// Finished tiles at or after `resume_from`.
+    pub(crate) completed: BTreeSet<u64>,
+    pub(crate) done: u64,
+    pub(crate) failed: u64,
+    pub(crate) bytes: u64,
+}
+
+impl PersistedJob {
+    pub(crate) fn serialize(&self) -> String {
+        let points = |points: &[LatitudeLongitudeCoordinates]| {
+            points
+                .iter()
+                .map(|point| format!("{},{}", point.latitude, point.longitude))
+                .collect::<Vec<_>>()
+                .join(";")
+        };
+        let region = match &self.job.region {
+            TileRegion::Polygon(ring) => format!("polygon={}", points(ring)),
+            TileRegion::Corridor {
+                route,
+                buffer_meters,
+            } => format!("corridor={};{}", buffer_meters, points(route)),
+        };
+        let completed = self
+            .completed
+            .iter()
+            .map(u64::to_string)
+            .collect::<Vec<_>>()
+            .join(",");
+        format!(
+            "name={}\nstate={}\ntile_size={}\ntiling_scheme={}\nzoom_levels={} {}\nresume_from={}\ncompleted={}\ndone={}\nfailed={}\nbytes={}\n{}\n",
+            escape(&self.job.name),
+            match self.state {
+                SlippyTileJobState::Running => "running",
+                SlippyTileJobState::Paused => "paused",
+                SlippyTileJobState::Completed => "completed",
+                SlippyTileJobState::Cancelled => "cancelled",
+                SlippyTileJobState::Refused => "refused",
+            },
+            self.job.tile_size.to_pixels(),
+            match self.tiling_scheme {
+                TilingScheme::WebMercator => "web_mercator",
+                TilingScheme::Geographic => "geographic",
+            },
+            self.job.min_zoom_level.to_u8(),
+            self.job.max_zoom_level.to_u8(),
+            self.resume_from,
+            completed,
+            self.done,
+            self.failed,
+            self.bytes,
+            region,
+        )
+    }
+
+    pub(crate) fn deserialize(data: &str) -> Option<PersistedJob> {
+        let fields = data
+            .lines()
+            .filter_map(|line| line.split_once('='))
+            .collect::<HashMap<_, _>>();
+        let points = |points: &str| {
+            points
+                .split(';')
+                .filter(|point| !point.is_empty())
+                .map(|point| {
+                    let (latitude, longitude) = point.split_once(',')?;
+                    Some(LatitudeLongitudeCoordinates {
+                        latitude: latitude.parse().ok()?,
+                        longitude: longitude.parse().ok()?,
+                    })
+                })
+                .collect::<Option<Vec<_>>>()
+        };
+        let region = if let Some(ring) = fields.get("polygon") {
+            TileRegion::Polygon(points(ring)?)
+        } else {
+            let (buffer_meters, route) = fields.get("corridor")?.split_once(';')?;
+            TileRegion::Corridor {
+                route: points(route)?,
+                buffer_meters: buffer_meters.parse().ok()?,
+            }
+        };
+        let (min_zoom_level, max_zoom_level) = fields.get("zoom_levels")?.split_once(' ')?;
+        let tile_size = TileSize::try_from(fields.get("tile_size")?.parse::<u32>().ok()?).ok()?;
+        let number = |key: &str| fields.get(key)?.parse::<u64>().ok();
+        Some(PersistedJob {
+            job: SlippyTileJob {
+                name: unescape(fields.get("name")?)?,
+                tile_size,
+                min_zoom_level: ZoomLevel::try_from(min_zoom_level.parse::<u8>().ok()?).ok()?,
+                max_zoom_level: ZoomLevel::try_from(max_zoom_level.parse::<u8>().ok()?).ok()?,
+                region,
+            },
+            // Jobs saved before tiling schemes were supported use Web Mercator tiles.
+            tiling_scheme: match fields.get("tiling_scheme").copied() {
+                None | Some("web_mercator") => TilingScheme::WebMercator,
+                Some("geographic") => TilingScheme::Geographic,
+                _ => return None,
+            },
+            state: match *fields.get("state")? {
+                "running" => SlippyTileJobState::Running,
+                "paused" => SlippyTileJobState::Paused,
+                "completed" => SlippyTileJobState::Completed,
+                "cancelled" => SlippyTileJobState::Cancelled,
+                "refused" => SlippyTileJobState::Refused,
+                _ => return None,
+            },
+            resume_from: number("resume_from")?,
+            completed: fields
+                .get("completed")?
+                .split(',')
+                .filter(|position| !position.is_empty())
+                .map(|position| position.parse().ok())
+                .collect::<Option<_>>()?,
+            done: number("done")?,
+            failed: number("failed")?,
+            bytes: number("bytes")?,
+        })
+    }
+}
+
+/// A job's tiles, numbered zoom level by zoom level - positions are how progress is tracked and saved.
+struct JobTiles {
+    tiling_scheme: TilingScheme,
+    zoom_levels: Vec<ZoomLevel>,
+    /// Number of tiles at each zoom level.
+    counts: Vec<u64>,
+    /// Tiles of the zoom level currently being downloaded.
+    current: Option<(usize, TileRows)>,
+}
+
+impl JobTiles {
+    fn new(job: &SlippyTileJob, tiling_scheme: TilingScheme) -> JobTiles {
+        let zoom_levels = (job.min_zoom_level.to_u8()..=job.max_zoom_level.to_u8())
+            .filter_map(|zoom| ZoomLevel::try_from(zoom).ok())
+            .collect::<Vec<_>>();
+        let counts = zoom_levels
+            .iter()
+            .map(|zoom_level| job.region.tile_count(*zoom_level, tiling_scheme))
+            .collect();
+        JobTiles {
+            tiling_scheme,
+            zoom_levels,
+            counts,
+            current: None,
+        }
+    }
+
+    fn total(&self) -> u64 {
+        self.counts.iter().sum()
+    }
+
+    fn get(&mut self, region: &TileRegion, mut position: u64) -> Option<SlippyTileDownloadTaskKey> {
+        let mut index = 0;
+        while position >= *self.counts.get(index)? {
+            position -= self.counts[index];
+            index += 1;
+        }
+        let zoom_level = self.zoom_levels[index];
+        if self.current.as_ref().map(|(current, _)| *current) != Some(index) {
+            self.current = Some((index, region.tile_rows(zoom_level, self.tiling_scheme)));
+        }
+        let (_, tiles) = self.current.as_ref()?;
+        Some(SlippyTileDownloadTaskKey {
+            slippy_tile_coordinates: tiles.get(position)?,
+            zoom_level,
+            tile_size: TileSize::Normal,
+        })
+    }
+}
+
+struct TileJob {
+    job: SlippyTileJob,
+    progress: SlippyTileJobProgress,
+    tiles: JobTiles,
+    /// Next position to check.
+    cursor: u64,
+    /// Finished positions at or after the resume position.
+    completed: BTreeSet<u64>,
+    lookups: Vec<(u64, SlippyTileDownloadTaskKey, Task<bool>)>,
+    missing: VecDeque<(u64, SlippyTileDownloadTaskKey)>,
+    /// Tiles being downloaded for a regular tile request, finished once that download completes.
+    shared: Vec<(u64, SlippyTileDownloadTaskKey)>,
+    downloads: Vec<(
+        u64,
+        SlippyTileDownloadTaskKey,
+        Task<SlippyTileDownloadTaskResult>,
+    )>,
+    last_progress: Option<(Instant, SlippyTileJobProgress)>,
+    last_persist: Option<(Instant, SlippyTileJobProgress)>,
+    persist_task: Option<Task<()>>,
+}
+
+impl TileJob {
+    fn new(persisted: PersistedJob) -> TileJob {
+        let tiles = JobTiles::new(&persisted.job, persisted.tiling_scheme);
+        TileJob {
+            progress: SlippyTileJobProgress {
+                name: persisted.job.name.clone(),
+                state: persisted.state,
+                total: tiles.total(),
+                done: persisted.done,
+                failed: persisted.failed,
+                bytes: persisted.bytes,
+            },
+            job: persisted.job,
+            tiles,
+            cursor: persisted.resume_from,
+            completed: persisted.completed,
+            lookups: Vec::new(),
+            missing: VecDeque::new(),
+            shared: Vec::new(),
+            downloads: Vec::new(),
+            last_progress: None,
+            last_persist: None,
+            persist_task: None,
+        }
+    }
+
+    fn key(&mut self, position: u64) -> Option<SlippyTileDownloadTaskKey> {
+        let mut key = self.tiles.get(&self.job.region, position)?;
+        key.tile_size = self.job.tile_size;
+        Some(key)
+    }
+
+    /// Every tile before this position is finished.
+    fn resume_from(&self) -> u64 {
+        self.lookups
+            .iter()
+            .map(|(position, ..)| *position)
+            .chain(self.missing.iter().map(|(position, _)| *position))
+            .chain(self.shared.iter().map(|(position, _)| *position))
+            .chain(self.downloads.iter().map(|(position, ..)| *position))
+            .fold(self.cursor, u64::min)
+    }
+
+    fn persisted(&self) -> PersistedJob {
+        PersistedJob {
+            job: self.job.clone(),
+            tiling_scheme: self.tiles.tiling_scheme,
+            state: self.progress.state,
+            resume_from: self.resume_from(),
+            completed: self.completed.clone(),
+            done: self.progress.done,
+            failed: self.progress.failed,
+            bytes: self.progress.bytes,
+        }
+    }
+
+    fn finish(&mut self, position: u64, succeeded: bool) {
+        if succeeded {
+            self.progress.done += 1;
+        } else {
+            self.progress.failed += 1;
+        }
+        self.completed.insert(position);
+    }
+
+    /// Stops checking tiles, they are checked again once the job resumes. Ongoing downloads are left to finish.
+    fn stop_lookups(&mut self) {
+        self.cursor = self
+            .lookups
+            .drain(..)
+            .map(|(position, ..)| position)
+            .chain(self.missing.drain(..).map(|(position, _)| position))
+            .chain(self.shared.drain(..).map(|(position, _)| position))
+            .fold(self.cursor, u64::min);
+    }
+
+    /// Cancels the ongoing downloads.
+    fn cancel_downloads(&mut self, rate_limiter: &mut DownloadRateLimiter) {
+        // Dropping the tasks cancels them.
+        for (_, key, _) in self.downloads.drain(..) {
+            rate_limiter.complete_job_download(&key, FileExists::No);
+        }
+    }
+
+    fn is_finished(&self) -> bool {
+        self.cursor >= self.progress.total
+            && self.lookups.is_empty()
+            && self.missing.is_empty()
+            && self.shared.is_empty()
+            && self.downloads.is_empty()
+    }
+}
+
+/// Escapes line breaks (and backslashes) so a value fits on its `key=value` line - the first `=` of a line ends the
+/// key, so values may contain `=`.
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('\n', "\\n")
+        .replace('\r', "\\r")
+}
+
+fn unescape(value: &str) -> Option<String> {
+    let mut unescaped = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        unescaped.push(match c {
+            '\\' => match chars.next()? {
+                '\\' => '\\',
+                'n' => '\n',
+                'r' => '\r',
+                _ => return None,
+            },
+            c => c,
+        });
+    }
+    Some(unescaped)
+}
+
+fn job_path(tiles_directory: &str, name: &str) -> PathBuf {
+    let filename = name
+        .chars()
+        .map(
+            |c| match c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                true => c,
+                false => '_',
+            },
+        )
+        .collect::<String>();
+    Path::new(tiles_directory)
+        .join(JOBS_DIRECTORY)
+        .join(format!("{}.job", filename))
+}
+
+fn spawn_persist_task(path: PathBuf, data: Option<String>, asset_server: &AssetServer) -> Task<()> {
+    let asset_server = asset_server.clone();
+    IoTaskPool::get().spawn(async move {
+        let asset_writer = match asset_server
+            .get_source(AssetSourceId::Default)
+            .map(|source| source.writer())
+        {
+            Ok(Ok(writer)) => writer,
+            _ => {
+                warn!("No asset writer to save slippy tile job progress");
+                return;
+            },
+        };
+        let Some(data) = data else {
+            if let Err(e) = asset_writer.remove(&path).await {
+                debug!("Failed to remove slippy tile job file {:?}: {:?}", path, e);
+            }
+            return;
+        };
+        let result = async {
+            let mut writer = asset_writer
+                .write(&path)
+                .await
+                .map_err(|e| format!("{:?}", e))?;
+            writer
+                .write_all(data.as_bytes())
+                .await
+                .map_err(|e| e.to_string())?;
+            writer.close().await.map_err(|e| e.to_string())
+        };
+        if let Err(e) = result.await {
+            warn!("Failed to save slippy tile job {:?}: {}", path, e);
+        }
+    })
+}
+
+fn spawn_restore_task(
+    tiles_directory: String,
+    asset_server: &AssetServer,
+) -> Task<Vec<PersistedJob>> {
+    let asset_server = asset_server.clone();
+    IoTaskPool::get().spawn(async move {
+        let mut jobs = Vec::new();
+        let Ok(asset_source) = asset_server.get_source(AssetSourceId::Default) else {
+            return jobs;
+        };
+        let asset_reader = asset_source.reader();
+        let directory = Path::new(&tiles_directory).join(JOBS_DIRECTORY);
+        let mut paths = match asset_reader.read_directory(&directory).await {
+            Ok(paths) => paths,
+            Err(AssetReaderError::NotFound(_)) => return jobs,
+            Err(e) => {
+                warn!("Failed to read slippy tile jobs: {:?}", e);
+                return jobs;
+            },
+        };
+        while let Some(path) = paths.next().await {
+            let mut data = Vec::new();
+            let read = match asset_reader.read(&path).await {
+                Ok(mut reader) => reader.read_to_end(&mut data).await.is_ok(),
+                Err(_) => false,
+            };
+            match std::str::from_utf8(&data)
+                .ok()
+                .and_then(PersistedJob::deserialize)
+            {
+                Some(job) if read => jobs.push(job),
+                _ => warn!("Ignoring invalid slippy tile job file {:?}", path),
+            }
+        }
+        jobs
+    })
+}
+
+/// System that restores the jobs saved by a previous run.
+pub fn restore_slippy_tile_jobs(
+    mut jobs: ResMut<SlippyTileJobs>,
+    slippy_tiles_settings: Res<SlippyTilesSettings>,
+    asset_server: Res<AssetServer>,
+) {
+    jobs.restore = Some(spawn_restore_task(
+        slippy_tiles_settings.get_tiles_directory_string(),
+        &asset_server,
+    ));
+}
+
+/// System that runs bulk download jobs.
+///
+/// Job tiles only use download slots of the [`DownloadRateLimiter`] that no other tile request is waiting on,
+/// and are not downloaded while `offline` mode is enabled.
+#[allow(clippy::too_many_arguments)]
+pub fn run_slippy_tile_jobs(
+    mut job_messages: MessageReader<SlippyTileJobMessage>,
+    mut progress_messages: MessageWriter<SlippyTileJobProgressMessage>,
+    mut jobs: ResMut<SlippyTileJobs>,
+    slippy_tiles_settings: Res<SlippyTilesSettings>,
+    mut rate_limiter: ResMut<DownloadRateLimiter>,
+    mut cache_index: ResMut<SlippyTileCacheIndex>,
+    slippy_tile_download_status: Res<SlippyTileDownloadStatus>,
+    asset_server: Res<AssetServer>,
+    fetcher: Res<SlippyTileFetcher>,
+    request_hook: Res<SlippyTileRequestHook>,
+    validator: Res<SlippyTileValidator>,
+    mut stats: ResMut<SlippyTileStats>,
+) {
+    let jobs = &mut *jobs;
+    let tiles_directory = slippy_tiles_settings.get_tiles_directory_string();
+    let now = Instant::now();
+
+    if let Some(restored) = jobs
+        .restore
+        .as_mut()
+        .and_then(|task| future::block_on(future::poll_once(task)))
+    {
+        jobs.restore = None;
+        for mut persisted in restored {
+            debug!("Restoring slippy tile job {}", persisted.job.name);
+            if persisted.tiling_scheme != slippy_tiles_settings.tiling_scheme {
+                // Its tiles are numbered differently, they are all checked again.
+                warn!(
+                    "Restarting slippy tile job {}: the tiling scheme changed",
+                    persisted.job.name
+                );
+                persisted = PersistedJob {
+                    tiling_scheme: slippy_tiles_settings.tiling_scheme,
+                    resume_from: 0,
+                    completed: BTreeSet::new(),
+                    done: 0,
+                    failed: 0,
+                    ..persisted
+                };
+            }
+            jobs.jobs
+                .entry(persisted.job.name.clone())
+                .or_insert_with(|| TileJob::new(persisted));
+        }
+    }
+
+    for message in job_messages.read() {
+        match message {
+            SlippyTileJobMessage::Start(job) => {
+                if matches!(
+                    jobs.progress(&job.name).map(|progress| progress.state),
+                    Some(SlippyTileJobState::Running | SlippyTileJobState::Paused)
+                ) {
+                    warn!("Slippy tile job {} is already started", job.name);
+                    continue;
+                }
+                jobs.jobs.insert(
+                    job.name.clone(),
+                    TileJob::new(PersistedJob {
+                        job: job.clone(),
+                        tiling_scheme: slippy_tiles_settings.tiling_scheme,
+                        state: SlippyTileJobState::Running,
+                        resume_from: 0,
+                        completed: BTreeSet::new(),
+                        done: 0,
+                        failed: 0,
+                        bytes: 0,
+                    }),
+                );
+            },
+            SlippyTileJobMessage::Pause(name) => {
+                if let Some(job) = jobs.jobs.get_mut(name) {
+                    if job.progress.state == SlippyTileJobState::Running {
+                        job.progress.state = SlippyTileJobState::Paused;
+                        job.stop_lookups();
+                    }
+                }
+            },
+            SlippyTileJobMessage::Resume(name) => {
+                if let Some(job) = jobs.jobs.get_mut(name) {
+                    if job.progress.state == SlippyTileJobState::Paused {
+                        job.progress.state = SlippyTileJobState::Running;
+                    }
+                }
+            },
+            SlippyTileJobMessage::Cancel(name) => {
+                if let Some(job) = jobs.jobs.get_mut(name) {
+                    if matches!(
+                        job.progress.state,
+                        SlippyTileJobState::Running | SlippyTileJobState::Paused
+                    ) {
+                        job.progress.state = SlippyTileJobState::Cancelled;
+                        job.stop_lookups();
+                        job.cancel_downloads(&mut rate_limiter);
+                    }
+                }
+            },
+        }
+    }
+
+    for job in jobs.jobs.values_mut() {
+        // Checked whenever a job runs, as it may have been started, resumed or restored with another endpoint.
+        if job.progress.state == SlippyTileJobState::Running
+            && breaks_osm_usage_policy(&job.job, &slippy_tiles_settings)
+        {
+            warn!(
+                "Refusing slippy tile job {}: the OpenStreetMap tile usage policy forbids bulk downloads above zoom level {}",
+                job.job.name,
+                OSM_MAX_BULK_ZOOM_LEVEL.to_u8()
+            );
+            job.progress.state = SlippyTileJobState::Refused;
+            job.stop_lookups();
+            job.cancel_downloads(&mut rate_limiter);
+        }
+
+        // Downloads complete even while paused.
+        let mut index = 0;
+        while index < job.downloads.len() {
+            let Some(result) = future::block_on(future::poll_once(&mut job.downloads[index].2))
+            else {
+                index += 1;
+                continue;
+            };
+            let (position, key, _) = job.downloads.swap_remove(index);
+            rate_limiter.complete_job_download(&key, FileExists::new(result.result.is_ok()));
+            if let Some(fetch_stats) = &result.fetch_stats {
+                stats.record_fetch(fetch_stats);
+                job.progress.bytes += fetch_stats.bytes;
+            }
+            match &result.result {
+                Ok(()) => cache_index.insert(key),
+                Err(error) => {
+                    warn!(
+                        "Slippy tile job {} failed to download {:?}: {}",
+                        job.job.name, result.path, error
+                    );
+                    stats.record_failure(error.kind());
+                },
+            }
+            job.finish(position, result.result.is_ok());
+        }
+
+        if job.progress.state == SlippyTileJobState::Running && !slippy_tiles_settings.offline {
+            advance_job(
+                job,
+                &slippy_tiles_settings,
+                &tiles_directory,
+                &mut rate_limiter,
+                &cache_index,
+                &slippy_tile_download_status,
+                &asset_server,
+                &FetchSettings::new(&slippy_tiles_settings, &fetcher, &request_hook, &validator),
+                &mut stats,
+            );
+            if job.is_finished() {
+                job.progress.state = SlippyTileJobState::Completed;
+            }
+        }
+
+        // Forget finished positions that are now before the resume position.
+        let resume_from = job.resume_from();
+        job.completed = job.completed.split_off(&resume_from);
+
+        let state_changed = job
+            .last_progress
+            .as_ref()
+            .is_none_or(|(_, last)| last.state != job.progress.state);
+        let progress_changed = job
+            .last_progress
+            .as_ref()
+            .is_none_or(|(_, last)| *last != job.progress);
+        if state_changed
+            || (progress_changed
+                && job
+                    .last_progress
+                    .as_ref()
+                    .is_some_and(|(at, _)| now.duration_since(*at) >= PROGRESS_INTERVAL))
+        {
+            progress_messages.write(SlippyTileJobProgressMessage(job.progress.clone()));
+            job.last_progress = Some((now, job.progress.clone()));
+        }
+
+        if job
+            .persist_task
+            .as_mut()
+            .is_some_and(|task| future::block_on(future::poll_once(task)).is_some())
+        {
+            job.persist_task = None;
+        }
+        // Saves never overlap, so they land in order. Running jobs are saved periodically, other changes right away.
+        let persist = match &job.last_persist {
+            None => true,
+            Some((at, last)) => {
+                *last != job.progress
+                    && (last.state != job.progress.state
+                        || job.progress.state != SlippyTileJobState::Running
+                        || now.duration_since(*at) >= PERSIST_INTERVAL)
+            },
+        };
+        if persist && job.persist_task.is_none() {
+            // Finished jobs have nothing left to resume.
+            let data = match job.progress.state {
+                SlippyTileJobState::Running | SlippyTileJobState::Paused => {
+                    Some(job.persisted().serialize())
+                },
+                SlippyTileJobState::Completed
+                | SlippyTileJobState::Cancelled
+                | SlippyTileJobState::Refused => None,
+            };
+            job.persist_task = Some(spawn_persist_task(
+                job_path(&tiles_directory, &job.job.name),
+                data,
+                &asset_server,
+            ));
+            job.last_persist = Some((now, job.progress.clone()));
+        }
+    }
+
+    // Cancelled jobs are forgotten once their save is removed.
+    jobs.jobs.retain(|_, job| {
+        job.progress.state != SlippyTileJobState::Cancelled
+            || job.persist_task.is_some()
+            || job
+                .last_persist
+                .as_ref()
+                .is_none_or(|(_, last)| last.state != SlippyTileJobState::Cancelled)
+    });
+}
+
+/// Whether downloading the job from the current endpoint would break the OpenStreetMap tile usage policy.
+fn breaks_osm_usage_policy(job: &SlippyTileJob, settings: &SlippyTilesSettings) -> bool {
+    settings.enforces_osm_usage_policy(endpoint_host(&settings.endpoint))
+        && job.max_zoom_level.to_u8() > OSM_MAX_BULK_ZOOM_LEVEL.to_u8()
+}
+
+#[allow(clippy::too_many_arguments)]
+fn advance_job(
+    job: &mut TileJob,
+    settings: &SlippyTilesSettings,
+    tiles_directory: &str,
+    rate_limiter: &mut DownloadRateLimiter,
+    cache_index: &SlippyTileCacheIndex,
+    slippy_tile_download_status: &SlippyTileDownloadStatus,
+    asset_server: &AssetServer,
+    fetch_settings: &FetchSettings,
+    stats: &mut SlippyTileStats,
+) {
+    let mut index = 0;
+    while index < job.shared.len() {
+        if rate_limiter.is_pending(&job.shared[index].1) {
+            index += 1;
+            continue;
+        }
+        let (position, key) = job.shared.swap_remove(index);
+        // Downloaded by another job.
+        if cache_index.contains(&key) {
+            job.finish(position, true);
+            continue;
+        }
+        match slippy_tile_download_status
+            .0
+            .get(&key)
+            .map(|status| &status.load_status)
+        {
+            Some(DownloadStatus::Downloaded) => job.finish(position, true),
+            Some(DownloadStatus::Failed(error)) if *error != TileDownloadError::Offline => {
+                job.finish(position, false)
+            },
+            // Dropped or unavailable offline, the job downloads it itself.
+            _ => job.missing.push_back((position, key)),
+        }
+    }
+
+    let mut index = 0;
+    while index < job.lookups.len() {
+        let Some(cached) = future::block_on(future::poll_once(&mut job.lookups[index].2)) else {
+            index += 1;
+            continue;
+        };
+        let (position, key, _) = job.lookups.swap_remove(index);
+        match cached {
+            true => job.finish(position, true),
+            false => job.missing.push_back((position, key)),
+        }
+    }
+
+    while job.lookups.len() + job.missing.len() + job.shared.len() < LOOKAHEAD
+        && job.cursor < job.progress.total
+    {
+        let position = job.cursor;
+        job.cursor += 1;
+        if job.completed.contains(&position)
+            || job
+                .downloads
+                .iter()
+                .any(|(downloading, ..)| *downloading == position)
+        {
+            continue;
+        }
+        let Some(key) = job.key(position) else {
+            continue;
+        };
+        if cache_index.contains(&key) {
+            job.finish(position, true);
+        } else if rate_limiter.is_pending(&key) {
+            job.shared.push((position, key));
+        } else if cache_index.is_ready() {
+            job.missing.push_back((position, key));
+        } else {
+            let filename = systems::get_tile_filename(
+                tiles_directory.to_owned(),
+                key.zoom_level,
+                key.slippy_tile_coordinates.x,
+                key.slippy_tile_coordinates.y,
+                key.tile_size,
+            );
+            let task = systems::spawn_cache_lookup_task(filename, asset_server);
+            job.lookups.push((position, key, task));
+        }
+    }
+
+    let host = endpoint_host(&settings.endpoint);
+    let max_downloads = settings.limits_for_host(host).max_concurrent_downloads;
+    while job.downloads.len() < max_downloads {
+        let Some((position, key)) = job.missing.pop_front() else {
+            break;
+        };
+        // Requested since it was found missing.
+        if rate_limiter.is_pending(&key) {
+            job.shared.push((position, key));
+            continue;
+        }
+        let Some(semaphore) = rate_limiter.try_acquire_idle(host, settings) else {
+            job.missing.push_front((position, key));
+            break;
+        };
+        let filename = systems::get_tile_filename(
+            tiles_directory.to_owned(),
+            key.zoom_level,
+            key.slippy_tile_coordinates.x,
+            key.slippy_tile_coordinates.y,
+            key.tile_size,
+        );
+        let task = systems::download_slippy_tile(
+            key.slippy_tile_coordinates,
+            key.zoom_level,
+            key.tile_size,
+            settings.endpoint.clone(),
+            filename,
+            asset_server,
+            semaphore,
+            rate_limiter.start_job_download(key.clone()),
+            fetch_settings.clone(),
+        );
+        stats.requests_issued += 1;
+        job.downloads.push((position, key, task));
+    }
+}
@@ -0,0 +1,167 @@
+use std::{fmt, str::FromStr};
+
+use crate::{
+    coordinates::LatitudeLongitudeCoordinates,
+    types::ParseCoordinatesError,
+    utm::{Mgrs, Utm},
+};
+
+/// Displays coordinates as decimal degrees, `45.410977, -75.698542`, with the number of decimals as the precision
+/// (`{:.2}`, 6 - about 10cm - by default).
+impl fmt::Display for LatitudeLongitudeCoordinates {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let precision = f.precision().unwrap_or(6);
+        write!(
+            f,
+            "{:.*}, {:.*}",
+            precision, self.latitude, precision, self.longitude
+        )
+    }
+}
+
+impl FromStr for LatitudeLongitudeCoordinates {
+    type Err = ParseCoordinatesError;
+
+    /// Parses coordinates typed in any of these formats:
+    /// - Decimal degrees, latitude first: `45.4110, -75.6985`, `45.4110 -75.6985` or `45.4110°N 75.6985°W`
+    /// - Degrees, minutes and seconds: `45°24'39.5"N 75°41'54.8"W`, `45 24 39.5 N, 75 41 54.8 W` or degrees and
+    ///   decimal minutes (`45°24.658'N 75°41.913'W`) - with hemisphere letters, the longitude may come first
+    /// - UTM: `18T 445338 5028843` (see [`Utm`])
+    /// - MGRS: `18TVR4533828843` (see [`Mgrs`])
+    ///
+    /// Geohashes can't be told apart from MGRS and are parsed with [`Geohash`](crate::Geohash) instead.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Ok(utm) = s.parse::<Utm>() {
+            return Ok(utm.to_latitude_longitude());
+        }
+        if let Some(coords) = s
+            .parse::<Mgrs>()
+            .ok()
+            .and_then(|mgrs| mgrs.to_latitude_longitude())
+        {
+            return Ok(coords);
+        }
+        let format_error = || ParseCoordinatesError::Format(s.into());
+        let (first, second) = split_angles(s).ok_or_else(format_error)?;
+        let (first, second) = (
+            parse_angle(first).ok_or_else(format_error)?,
+            parse_angle(second).ok_or_else(format_error)?,
+        );
+        let (latitude, longitude) = match (first.1, second.1) {
+            (Some('E' | 'W'), None | Some('N' | 'S')) | (None, Some('N' | 'S')) => {
+                (second.0, first.0)
+            },
+            (None | Some('N' | 'S'), None | Some('E' | 'W')) => (first.0, second.0),
+            _ => return Err(format_error()),
+        };
+        if !(-90.0..=90.0).contains(&latitude) || !(-180.0..=180.0).contains(&longitude) {
+            return Err(ParseCoordinatesError::OutOfRange(s.into()));
+        }
+        Ok(LatitudeLongitudeCoordinates {
+            latitude,
+            longitude,
+        })
+    }
+}
+
+/// Displays coordinates as degrees, minutes and seconds, `45°24'39.52"N 75°41'54.75"W`, with the number of decimals
+/// of the seconds as the precision (`{:.0}`, 2 - about 30cm - by default, at most 9).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DegreesMinutesSeconds(pub LatitudeLongitudeCoordinates);
+
+impl fmt::Display for DegreesMinutesSeconds {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Beyond 9 decimals, the seconds no longer fit in the integer rounding below.
+        let precision = f.precision().unwrap_or(2).min(9);
+        let write_angle = |f: &mut fmt::Formatter<'_>, angle: f64, hemispheres: [char; 2]| {
+            // Round first, so 59.999" carries over to the minutes and degrees.
+            let scale = 10u64.pow(precision as u32);
+            let units = (angle.abs() * 3600.0 * scale as f64).round() as u64;
+            let (degrees, minutes) = (units / (3600 * scale), units / (60 * scale) % 60);
+            let seconds = (units % (60 * scale)) as f64 / scale as f64;
+            let hemisphere = hemispheres[usize::from(angle < 0.0)];
+            write!(
+                f,
+                "{}°{:02}'{:0width$.precision$}\"{}",
+                degrees,
+                minutes,
+                seconds,
+                hemisphere,
+                width = precision + if precision > 0 { 3 } else { 2 },
+                precision = precision
+            )
+        };
+        write_angle(f, self.0.latitude, ['N', 'S'])?;
+        f.write_str(" ")?;
+        write_angle(f, self.0.longitude, ['E', 'W'])
+    }
+}
+
+impl LatitudeLongitudeCoordinates {
+    /// Degrees, minutes and seconds display of the coordinates, `45°24'39.52"N 75°41'54.75"W`.
+    pub fn to_dms(&self) -> DegreesMinutesSeconds {
+        DegreesMinutesSeconds(*self)
+    }
+}
+
+// Splits the text in two angles: at a comma, at the hemisphere letters or at the only space.
+fn split_angles(text: &str) -> Option<(&str, &str)> {
+    let text = text.trim();
+    if let Some(angles) = text.split_once(',') {
+        return Some(angles);
+    }
+    let is_hemisphere = |c: char| "NSEWnsew".contains(c);
+    if text.starts_with(is_hemisphere) {
+        // Letters before the angles, `N45 W75`.
+        if let Some(index) = text[1..].find(is_hemisphere) {
+            return Some(text.split_at(index + 1));
+        }
+    } else if let Some(index) = text.find(is_hemisphere) {
+        // Letters after the angles, `45N 75W`.
+        if !text[index + 1..].trim().is_empty() {
+            return Some(text.split_at(index + 1));
+        }
+    }
+    let mut tokens = text.split_whitespace();
+    match (tokens.next(), tokens.next(), tokens.next()) {
+        (Some(first), Some(second), None) => Some((first, second)),
+        _ => None,
+    }
+}
+
+// Parses `45.41`, `-45°24'39.5"`, `45 24 39.5 N`, `S45°24.658'`... into signed degrees and the hemisphere letter.
+fn parse_angle(text: &str) -> Option<(f64, Option<char>)> {
+    let text = text.trim();
+    let hemisphere = text
+        .chars()
+        .next()
+        .filter(char::is_ascii_alphabetic)
+        .or_else(|| text.chars().last().filter(char::is_ascii_alphabetic))
+        .map(|c| c.to_ascii_uppercase());
+    let text = text.trim_matches(|c: char| c.is_ascii_alphabetic()).trim();
+    if hemisphere.is_some_and(|c| !"NSEW".contains(c)) {
+        return None;
+    }
+
+    let mut parts = text
+        .split(|c: char| c.is_whitespace() || "°º'′\"″:".contains(c))
+        .filter(|part| !part.is_empty());
+    let degrees = parts.next()?.parse::<f64>().ok()?;
+    let minutes = parts
+        .next()
+        .map_or(Some(0.0), |part| part.parse::<f64>().ok())?;
+    let seconds = parts
+        .next()
+        .map_or(Some(0.0), |part| part.parse::<f64>().ok())?;
+    if parts.next().is_some()
+        || !degrees.is_finite()
+        || !(0.0..60.0).contains(&minutes)
+        || !(0.0..60.0).contains(&seconds)
+        || (hemisphere.is_some() && degrees < 0.0)
+    {
+        return None;
+    }
+    let angle = degrees.abs() + minutes / 60.0 + seconds / 3600.0;
+    let negative = degrees.is_sign_negative() || matches!(hemisphere, Some('S' | 'W'));
+    Some((if negative { -angle } else { angle }, hemisphere))
+}
@@ -0,0 +1,65 @@
+use std::f64::consts::PI;
+
+use crate::{
+    constants::MAX_LATITUDE,
+    coordinates::LatitudeLongitudeCoordinates,
+    types::{TileSize, ZoomLevel},
+};
+
+/// Web Mercator projection between latitude/longitude and pixels of the whole map, for a tile size and zoom level.
+///
+/// Pixel (0, 0) is the north-west (top left) corner of the map. Any tile size works, not only powers of two.
+/// The projection is `Copy` and allocation-free: create it once and reuse it to project many points.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WebMercatorProjection {
+    world_pixels: f64,
+    pixels_per_degree: f64,
+    pixels_per_radian: f64,
+}
+
+impl WebMercatorProjection {
+    pub fn new(tile_size: TileSize, zoom_level: ZoomLevel) -> WebMercatorProjection {
+        let world_pixels = tile_size.to_pixels() as f64 * (1u64 << zoom_level.to_u8()) as f64;
+        WebMercatorProjection {
+            world_pixels,
+            pixels_per_degree: world_pixels / 360.0,
+            pixels_per_radian: world_pixels / (2.0 * PI),
+        }
+    }
+
+    /// Width (and height) of the map, in pixels.
+    pub fn world_pixels(&self) -> f64 {
+        self.world_pixels
+    }
+
+    /// Pixel position of a lat/lon, latitudes being clamped to the Web Mercator limits.
+    pub fn to_pixel(&self, coords: &LatitudeLongitudeCoordinates) -> (f64, f64) {
+        let center = self.world_pixels / 2.0;
+        let sin_latitude = coords
+            .latitude
+            .clamp(-MAX_LATITUDE, MAX_LATITUDE)
+            .to_radians()
+            .sin();
+        (
+            center + coords.longitude * self.pixels_per_degree,
+            center
+                + 0.5
+                    * ((1.0 + sin_latitude) / (1.0 - sin_latitude)).ln()
+                    * -self.pixels_per_radian,
+        )
+    }
+
+    /// Lat/lon of a pixel position.
+    pub fn to_latitude_longitude(
+        &self,
+        x_pixel: f64,
+        y_pixel: f64,
+    ) -> LatitudeLongitudeCoordinates {
+        let center = self.world_pixels / 2.0;
+        let y = (y_pixel - center) / -self.pixels_per_radian;
+        LatitudeLongitudeCoordinates {
+            latitude: (2.0 * y.exp().atan() - 0.5 * PI).to_degrees(),
+            longitude: (x_pixel - center) / self.pixels_per_degree,
+        }
+    }
+}
@@ -0,0 +1,335 @@
+use std::{
+    collections::BTreeMap,
+    ops::{Range, RangeInclusive},
+};
+
+use crate::{
+    constants::{EARTH_CIRCUMFERENCE, MAX_LATITUDE},
+    coordinates::{LatitudeLongitudeCoordinates, SlippyTileCoordinates},
+    tiling::TilingScheme,
+    types::ZoomLevel,
+};
+
+/// An area of the map to request tiles for.
+///
+/// Edges are straight lines on the map of the [`TilingScheme`] (as they are drawn on the tiles), and always take the
+/// shortest way around the globe, so regions can cross the antimeridian.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TileRegion {
+    /// A polygon ring, implicitly closed (the last point connects back to the first one).
+    Polygon(Vec<LatitudeLongitudeCoordinates>),
+    /// Every point within `buffer_meters` of a route.
+    Corridor {
+        route: Vec<LatitudeLongitudeCoordinates>,
+        buffer_meters: f64,
+    },
+}
+
+impl TileRegion {
+    /// Get every slippy tile intersecting the region at the given zoom level, row by row from the north-west.
+    pub fn tiles(
+        &self,
+        zoom_level: ZoomLevel,
+        tiling_scheme: TilingScheme,
+    ) -> Vec<SlippyTileCoordinates> {
+        self.tile_rows(zoom_level, tiling_scheme).iter().collect()
+    }
+
+    /// Number of slippy tiles intersecting the region at the given zoom level, without listing them.
+    pub fn tile_count(&self, zoom_level: ZoomLevel, tiling_scheme: TilingScheme) -> u64 {
+        self.tile_rows(zoom_level, tiling_scheme).len()
+    }
+
+    /// The tiles intersecting the region at the given zoom level, as column ranges.
+    pub(crate) fn tile_rows(&self, zoom_level: ZoomLevel, tiling_scheme: TilingScheme) -> TileRows {
+        let columns = tiling_scheme.columns(zoom_level);
+        let rows = tiling_scheme.rows(zoom_level);
+        let mut covered = BTreeMap::<u32, Vec<Range<u32>>>::new();
+        let mut cover = |y: i64, first: i64, last: i64| {
+            if !(0..rows as i64).contains(&y) {
+                return;
+            }
+            let ranges = covered.entry(y as u32).or_default();
+            let width = last - first + 1;
+            if width >= columns as i64 {
+                ranges.push(0..columns);
+                return;
+            }
+            let start = first.rem_euclid(columns as i64) as u32;
+            let end = start + width as u32;
+            if end <= columns {
+                ranges.push(start..end);
+            } else {
+                // Crosses the antimeridian.
+                ranges.push(start..columns);
+                ranges.push(0..end - columns);
+            }
+        };
+        match self {
+            TileRegion::Polygon(ring) => rasterize_polygon(
+                &to_tile_points(ring, zoom_level, tiling_scheme),
+                columns,
+                &mut cover,
+            ),
+            TileRegion::Corridor {
+                route,
+                buffer_meters,
+            } => {
+                let points = to_tile_points(route, zoom_level, tiling_scheme);
+                // A single point route is a circle around that point.
+                let segments = points
+                    .windows(2)
+                    .map(|segment| (segment[0], segment[1]))
+                    .chain(
+                        points
+                            .first()
+                            .filter(|_| points.len() == 1)
+                            .map(|p| (*p, *p)),
+                    );
+                for (index, (start, end)) in segments.enumerate() {
+                    // Use the scale furthest from the equator, so the corridor is never narrower than requested (away
+                    // from the equator, geographic tiles are taller than they are wide and are measured by their width).
+                    let latitude = route[index]
+                        .latitude
+                        .abs()
+                        .max(route[(index + 1).min(route.len() - 1)].latitude.abs())
+                        .min(MAX_LATITUDE);
+                    let meters_per_tile =
+                        EARTH_CIRCUMFERENCE * latitude.to_radians().cos() / columns as f64;
+                    rasterize_capsule(
+                        start,
+                        end,
+                        buffer_meters.max(0.0) / meters_per_tile,
+                        columns,
+                        &mut cover,
+                    );
+                }
+            },
+        }
+
+        let mut spans = Vec::new();
+        let mut len = 0;
+        for (y, mut ranges) in covered {
+            ranges.sort_unstable_by_key(|range| range.start);
+            let mut merged: Vec<Range<u32>> = Vec::with_capacity(ranges.len());
+            for range in ranges {
+                match merged.last_mut() {
+                    Some(last) if range.start <= last.end => last.end = last.end.max(range.end),
+                    _ => merged.push(range),
+                }
+            }
+            for columns in merged {
+                spans.push(TileSpan {
+                    first: len,
+                    y,
+                    columns: columns.clone(),
+                });
+                len += columns.len() as u64;
+            }
+        }
+        TileRows { spans, len }
+    }
+}
+
+/// A run of tiles within a row, numbered from `first`.
+#[derive(Debug, Clone)]
+struct TileSpan {
+    first: u64,
+    y: u32,
+    columns: Range<u32>,
+}
+
+/// The tiles of a [`TileRegion`] at a zoom level, row by row from the north-west - stored as column ranges rather than
+/// tiles, so large regions can be counted and walked through without listing every tile.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct TileRows {
+    spans: Vec<TileSpan>,
+    len: u64,
+}
+
+impl TileRows {
+    pub(crate) fn len(&self) -> u64 {
+        self.len
+    }
+
+    /// The tile at the given position.
+    pub(crate) fn get(&self, position: u64) -> Option<SlippyTileCoordinates> {
+        if position >= self.len {
+            return None;
+        }
+        let span = &self.spans[self.spans.partition_point(|span| span.first <= position) - 1];
+        Some(SlippyTileCoordinates {
+            x: span.columns.start + (position - span.first) as u32,
+            y: span.y,
+        })
+    }
+
+    pub(crate) fn iter(&self) -> impl Iterator<Item = SlippyTileCoordinates> + '_ {
+        self.spans.iter().flat_map(|span| {
+            span.columns
+                .clone()
+                .map(|x| SlippyTileCoordinates { x, y: span.y })
+        })
+    }
+}
+
+type Point = (f64, f64);
+
+/// Converts coordinates to fractional tile coordinates, unwrapping longitudes so consecutive points are never more than
+/// half the world apart (x may then fall outside of the map, it is wrapped back once tiles are known).
+fn to_tile_points(
+    coords: &[LatitudeLongitudeCoordinates],
+    zoom_level: ZoomLevel,
+    tiling_scheme: TilingScheme,
+) -> Vec<Point> {
+    let columns = tiling_scheme.columns(zoom_level) as f64;
+    let mut previous_longitude: Option<f64> = None;
+    coords
+        .iter()
+        .map(|coords| {
+            let mut longitude = coords.longitude;
+            if let Some(previous) = previous_longitude {
+                longitude = previous + (longitude - previous + 180.0).rem_euclid(360.0) - 180.0;
+            }
+            previous_longitude = Some(longitude);
+            (
+                columns * (longitude + 180.0) / 360.0,
+                tiling_scheme
+                    .to_fractional_tile_coordinates(coords, zoom_level)
+                    .y,
+            )
+        })
+        .collect()
+}
+
+/// Columns of the tiles overlapping `lo..=hi`.
+fn columns(lo: f64, hi: f64, tiles: u32) -> RangeInclusive<i64> {
+    let first = lo.floor() as i64;
+    let last = (hi.ceil() as i64 - 1).max(first);
+    // No need to go around the world more than once.
+    first..=last.min(first + tiles as i64 - 1)
+}
+
+/// Horizontal extent of the part of segment `start..end` between rows `y0` and `y1`, if any.
+fn clip_to_band(start: Point, end: Point, y0: f64, y1: f64) -> Option<(f64, f64)> {
+    let (dx, dy) = (end.0 - start.0, end.1 - start.1);
+    let (t0, t1) = if dy == 0.0 {
+        if start.1 < y0 || start.1 > y1 {
+            return None;
+        }
+        (0.0, 1.0)
+    } else {
+        let (ta, tb) = ((y0 - start.1) / dy, (y1 - start.1) / dy);
+        (ta.min(tb).max(0.0), ta.max(tb).min(1.0))
+    };
+    if t0 > t1 {
+        return None;
+    }
+    let (xa, xb) = (start.0 + dx * t0, start.0 + dx * t1);
+    Some((xa.min(xb), xa.max(xb)))
+}
+
+/// Covers the polygon, calling `cover(row, first column, last column)` for runs of tiles.
+fn rasterize_polygon(ring: &[Point], tiles: u32, cover: &mut impl FnMut(i64, i64, i64)) {
+    if ring.is_empty() {
+        return;
+    }
+    let edges = (0..ring.len())
+        .map(|i| (ring[i], ring[(i + 1) % ring.len()]))
+        .collect::<Vec<_>>();
+    let min_y = ring.iter().map(|p| p.1).fold(f64::INFINITY, f64::min);
+    let max_y = ring.iter().map(|p| p.1).fold(f64::NEG_INFINITY, f64::max);
+
+    for row in columns(min_y, max_y, u32::MAX) {
+        let (y0, y1) = ((row as f64).max(min_y), ((row + 1) as f64).min(max_y));
+        // Any point of the polygon within this row either lies on an edge, or can be moved vertically to the middle of the
+        // row without leaving the polygon - the edges plus the interior spans along the middle cover the whole row.
+        let mut spans = edges
+            .iter()
+            .filter_map(|(start, end)| clip_to_band(*start, *end, y0, y1))
+            .collect::<Vec<_>>();
+        let middle = (y0 + y1) / 2.0;
+        let mut crossings = edges
+            .iter()
+            .filter(|(start, end)| (start.1 <= middle) != (end.1 <= middle))
+            .map(|(start, end)| {
+                start.0 + (middle - start.1) / (end.1 - start.1) * (end.0 - start.0)
+            })
+            .collect::<Vec<_>>();
+        crossings.sort_unstable_by(f64::total_cmp);
+        spans.extend(crossings.chunks_exact(2).map(|pair| (pair[0], pair[1])));
+
+        for (lo, hi) in spans {
+            let columns = columns(lo, hi, tiles);
+            cover(row, *columns.start(), *columns.end());
+        }
+    }
+}
+
+/// Covers every tile within `radius` of segment `start..end`, calling `cover(row, first column, last column)` for runs of
+/// tiles.
+fn rasterize_capsule(
+    start: Point,
+    end: Point,
+    radius: f64,
+    tiles: u32,
+    cover: &mut impl FnMut(i64, i64, i64),
+) {
+    let min_y = start.1.min(end.1) - radius;
+    let max_y = start.1.max(end.1) + radius;
+    for row in columns(min_y, max_y, u32::MAX) {
+        let (y0, y1) = (row as f64, (row + 1) as f64);
+        // Only tiles close enough to the part of the segment near this row can be within reach.
+        let Some((lo, hi)) = clip_to_band(start, end, y0 - radius, y1 + radius) else {
+            continue;
+        };
+        // The capsule is convex, so the tiles it covers within a row are contiguous.
+        let mut covered = None;
+        for column in columns(lo - radius, hi + radius, tiles) {
+            let tile = ((column as f64, y0), (column as f64 + 1.0, y1));
+            if segment_to_box_distance(start, end, tile) <= radius {
+                covered = Some((covered.map_or(column, |(first, _)| first), column));
+            }
+        }
+        if let Some((first, last)) = covered {
+            cover(row, first, last);
+        }
+    }
+}
+
+fn point_to_box_distance(p: Point, (min, max): (Point, Point)) -> f64 {
+    let dx = (min.0 - p.0).max(p.0 - max.0).max(0.0);
+    let dy = (min.1 - p.1).max(p.1 - max.1).max(0.0);
+    dx.hypot(dy)
+}
+
+fn point_to_segment_distance(p: Point, start: Point, end: Point) -> f64 {
+    let (dx, dy) = (end.0 - start.0, end.1 - start.1);
+    let length_squared = dx * dx + dy * dy;
+    let t = if length_squared == 0.0 {
+        0.0
+    } else {
+        (((p.0 - start.0) * dx + (p.1 - start.1) * dy) / length_squared).clamp(0.0, 1.0)
+    };
+    (p.0 - start.0 - t * dx).hypot(p.1 - start.1 - t * dy)
+}
+
+fn segment_to_box_distance(start: Point, end: Point, tile: (Point, Point)) -> f64 {
+    let (min, max) = tile;
+    // The segment goes through the box if its part within the box's rows overlaps the box's columns.
+    if let Some((lo, hi)) = clip_to_band(start, end, min.1, max.1) {
+        if lo <= max.0 && hi >= min.0 {
+            return 0.0;
+        }
+    }
+    // Otherwise the closest points include an endpoint of the segment or a corner of the box.
+    let corners = [min, (max.0, min.1), max, (min.0, max.1)];
+    corners
+        .iter()
+        .map(|corner| point_to_segment_distance(*corner, start, end))
+        .chain([
+            point_to_box_distance(start, tile),
+            point_to_box_distance(end, tile),
+        ])
+        .fold(f64::INFINITY, f64::min)
+}
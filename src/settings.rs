@@ -1,20 +1,170 @@
 use bevy::prelude::{Resource, Transform};
+use bevy_platform::collections::HashMap;
 use std::{path::PathBuf, time::Duration};
 
+use crate::{tiling::TilingScheme, types::ZoomLevel, validation::TileValidation};
+
+/// User-Agent sent with tile requests unless `user_agent` is changed.
+pub const DEFAULT_USER_AGENT: &str =
+    "bevy_slippy_tiles/0.7.0 (https://github.com/edouardpoitras/bevy_slippy_tiles)";
+
+/// Maximum number of concurrent downloads from an OpenStreetMap tile host when `osm_usage_policy` is enabled.
+pub const OSM_MAX_CONCURRENT_DOWNLOADS: usize = 2;
+/// Minimum time an OpenStreetMap tile is served from the cache before it can be downloaded again,
+/// when `osm_usage_policy` is enabled.
+pub const OSM_MIN_CACHE_TTL: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+/// Highest zoom level [`SlippyTileJob`](crate::SlippyTileJob)s may download from an OpenStreetMap tile host,
+/// when `osm_usage_policy` is enabled.
+pub const OSM_MAX_BULK_ZOOM_LEVEL: ZoomLevel = ZoomLevel::L12;
+/// Most tiles above [`OSM_MAX_BULK_ZOOM_LEVEL`] a single bounds or region request may download from an OpenStreetMap
+/// tile host when `osm_usage_policy` is enabled - about what a large screen shows.
+pub const OSM_MAX_BULK_TILES: u64 = 64;
+
+/// Whether the host serves the OpenStreetMap standard tile layer, subject to the tile usage policy:
+/// <https://operations.osmfoundation.org/policies/tiles/>
+pub fn is_osm_host(host: &str) -> bool {
+    let host = host.split(':').next().unwrap_or(host);
+    ["tile.openstreetmap.org", "tile.osm.org"]
+        .iter()
+        .any(|osm_host| host == *osm_host || host.ends_with(&format!(".{}", osm_host)))
+}
+
+/// How tile requests sent to a single host are rate limited.
+#[derive(Clone, Debug, PartialEq)]
+pub enum RateLimitPolicy {
+    /// At most `requests` requests within any `window` of time.
+    SlidingWindow { requests: usize, window: Duration },
+    /// Allows bursts of up to `burst` requests, refilled at a sustained rate of `per_second` requests per second.
+    TokenBucket { burst: usize, per_second: f64 },
+}
+
+/// HTTP/HTTPS proxy used for tile requests.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ProxySettings {
+    /// Proxy URL without credentials (example: `http://proxy.example.com:3128`).
+    pub url: String,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    /// Hosts that are reached directly. Entries match a host and its subdomains (`example.com` or `.example.com`), `*` matches every host.
+    pub no_proxy: Vec<String>,
+}
+
+impl ProxySettings {
+    /// Create proxy settings from a proxy URL, extracting any `user:password@` credentials from it.
+    pub fn from_url(proxy_url: &str) -> ProxySettings {
+        let (scheme, rest) = proxy_url.split_once("://").unwrap_or(("http", proxy_url));
+        let (credentials, address) = match rest.rsplit_once('@') {
+            Some((credentials, address)) => (Some(credentials), address),
+            None => (None, rest),
+        };
+        let (username, password) = match credentials.map(|c| c.split_once(':')) {
+            Some(Some((username, password))) => (Some(username.into()), Some(password.into())),
+            Some(None) => (credentials.map(Into::into), None),
+            None => (None, None),
+        };
+        ProxySettings {
+            url: format!("{}://{}", scheme, address.trim_end_matches('/')),
+            username,
+            password,
+            no_proxy: Vec::new(),
+        }
+    }
+
+    /// Read the proxy from the standard `HTTPS_PROXY` (falling back to `HTTP_PROXY`) and `NO_PROXY` environment variables.
+    /// Returns `None` if no proxy is configured.
+    pub fn from_env() -> Option<ProxySettings> {
+        let env = |names: &[&str]| {
+            names
+                .iter()
+                .find_map(|name| std::env::var(name).ok().filter(|value| !value.is_empty()))
+        };
+        let mut proxy = ProxySettings::from_url(&env(&[
+            "HTTPS_PROXY",
+            "https_proxy",
+            "HTTP_PROXY",
+            "http_proxy",
+        ])?);
+        proxy.no_proxy = env(&["NO_PROXY", "no_proxy"])
+            .map(|no_proxy| {
+                no_proxy
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|entry| !entry.is_empty())
+                    .map(Into::into)
+                    .collect()
+            })
+            .unwrap_or_default();
+        Some(proxy)
+    }
+
+    /// Whether requests to `host` (optionally with a port) should skip the proxy.
+    pub fn bypasses(&self, host: &str) -> bool {
+        let host = host
+            .rsplit_once(':')
+            .filter(|(_, port)| port.parse::<u16>().is_ok())
+            .map_or(host, |(host, _)| host)
+            .to_ascii_lowercase();
+        self.no_proxy.iter().any(|entry| {
+            let entry = entry.trim_start_matches('.').to_ascii_lowercase();
+            entry == "*"
+                || host == entry
+                || host
+                    .strip_suffix(&entry)
+                    .is_some_and(|subdomain| subdomain.ends_with('.'))
+        })
+    }
+
+    /// Proxy URL with the credentials included, as expected by most HTTP clients.
+    pub fn url_with_credentials(&self) -> String {
+        let Some(username) = &self.username else {
+            return self.url.clone();
+        };
+        let (scheme, address) = self.url.split_once("://").unwrap_or(("http", &self.url));
+        match &self.password {
+            Some(password) => format!("{}://{}:{}@{}", scheme, username, password, address),
+            None => format!("{}://{}@{}", scheme, username, address),
+        }
+    }
+}
+
+/// Download limits applied to a single tile host.
+#[derive(Clone, Debug, PartialEq)]
+pub struct HostLimits {
+    pub max_concurrent_downloads: usize,
+    pub rate_limit_policy: RateLimitPolicy,
+}
+
 macro_rules! generate_slippy_tiles_settings {
     ($(($name:ident, $type:ty, $default:expr)),* $(,)?) => {
         /// Type used to dictate various settings for this crate.
         ///
         /// Download Settings:
         /// - `endpoint` - Tile server endpoint (example: <https://tile.openstreetmap.org>)
+        /// - `tiling_scheme` - How the tile server cuts the world into tiles ([`TilingScheme::WebMercator`] or [`TilingScheme::Geographic`])
         /// - `tiles_directory` - The folder that all tiles will be stored in
         /// - `max_concurrent_downloads` - Maximum number of concurrent tile downloads
         /// - `max_retries` - Maximum number of retry attempts for failed downloads
+        /// - `connect_timeout` - Maximum time to connect to the tile server, and to wait on it while it doesn't send anything (retried on timeout)
+        /// - `request_timeout` - Maximum time for a whole tile request, including the response body (retried on timeout)
+        /// - `stalled_download_timeout` - Tile downloads still waiting on the tile server this long after getting a download slot are abandoned and marked as failed (time waiting for a slot doesn't count)
+        /// - `failed_tile_retry_delay` - How long a tile that failed for a transient reason (timeout, network error, stall, server error) is skipped before being requested again, rejected tiles and 4xx responses are only requested again when the cache is bypassed
+        /// - `proxy` - Optional HTTP/HTTPS proxy for tile requests (see [`ProxySettings::from_env`] to use the standard environment variables)
         /// - `rate_limit_requests` - Maximum number of requests allowed within the rate limit window
         /// - `rate_limit_window` - Duration of the rate limit window
+        /// - `rate_limit_policy` - Optional rate limit policy, replaces the `rate_limit_requests`/`rate_limit_window` sliding window
+        /// - `host_limits` - Per-host overrides of the concurrency and rate limits (keyed by host, example: `tile.openstreetmap.org`)
+        /// - `clear_status_on_source_change` - Whether changing `endpoint` or `tiles_directory` at runtime clears the in-memory download status
+        /// - `cache_index` - Whether to index the tiles directory in memory at startup, so cache lookups don't touch the disk
+        /// - `user_agent` - User-Agent sent with tile requests, identifying your application (required by most tile servers)
+        /// - `osm_usage_policy` - Enforce the OpenStreetMap tile usage policy for OpenStreetMap hosts (see [`is_osm_host`]):
+        ///   at most [`OSM_MAX_CONCURRENT_DOWNLOADS`] downloads, tiles kept at least [`OSM_MIN_CACHE_TTL`] and
+        ///   no bulk download jobs above [`OSM_MAX_BULK_ZOOM_LEVEL`], and no bounds or region requests for more than
+        ///   [`OSM_MAX_BULK_TILES`] tiles above it
+        /// - `offline` - Serve cached tiles only, uncached tiles fail with [`TileDownloadError::Offline`](crate::TileDownloadError::Offline) and are requeued once back online
+        /// - `validation` - Checks downloaded tiles must pass before being cached (content type, size, dimensions)
         ///
         /// Display Settings:
-        /// - `reference_latitude` - Latitude that maps to Transform(0,0,0) or transform_offset if specified
+        /// - `reference_latitude` - Latitude that maps to Transform(0,0,0) or transform_offset if specified (tiles are positioned using `tiling_scheme`)
         /// - `reference_longitude` - Longitude that maps to Transform(0,0,0) or transform_offset if specified
         /// - `transform_offset` - Optional offset from 0,0 where the reference coordinates should appear
         /// - `z_layer` - Z coordinate for rendered tiles
@@ -23,11 +173,25 @@ macro_rules! generate_slippy_tiles_settings {
         pub struct SlippyTilesSettings {
             // Download settings
             pub endpoint: String,
+            pub tiling_scheme: TilingScheme,
             pub tiles_directory: PathBuf,
             pub max_concurrent_downloads: usize,
             pub max_retries: u32,
+            pub connect_timeout: Option<Duration>,
+            pub request_timeout: Option<Duration>,
+            pub stalled_download_timeout: Option<Duration>,
+            pub failed_tile_retry_delay: Duration,
+            pub proxy: Option<ProxySettings>,
             pub rate_limit_requests: usize,
             pub rate_limit_window: Duration,
+            pub rate_limit_policy: Option<RateLimitPolicy>,
+            pub host_limits: HashMap<String, HostLimits>,
+            pub clear_status_on_source_change: bool,
+            pub cache_index: bool,
+            pub user_agent: String,
+            pub osm_usage_policy: bool,
+            pub offline: bool,
+            pub validation: TileValidation,
 
             // Other settings
             $(
@@ -39,6 +203,29 @@ macro_rules! generate_slippy_tiles_settings {
             pub fn get_tiles_directory_string(&self) -> String {
                 self.tiles_directory.as_path().to_str().unwrap().to_string()
             }
+
+            /// Download limits for the given host - each host gets its own concurrency and rate limit budget.
+            pub fn limits_for_host(&self, host: &str) -> HostLimits {
+                let mut limits = self.host_limits.get(host).cloned().unwrap_or_else(|| HostLimits {
+                    max_concurrent_downloads: self.max_concurrent_downloads,
+                    rate_limit_policy: self.rate_limit_policy.clone().unwrap_or(
+                        RateLimitPolicy::SlidingWindow {
+                            requests: self.rate_limit_requests,
+                            window: self.rate_limit_window,
+                        },
+                    ),
+                });
+                if self.enforces_osm_usage_policy(host) {
+                    limits.max_concurrent_downloads =
+                        limits.max_concurrent_downloads.min(OSM_MAX_CONCURRENT_DOWNLOADS);
+                }
+                limits
+            }
+
+            /// Whether the OpenStreetMap tile usage policy applies to requests sent to the given host.
+            pub fn enforces_osm_usage_policy(&self, host: &str) -> bool {
+                self.osm_usage_policy && is_osm_host(host)
+            }
         }
 
         impl Default for SlippyTilesSettings {
@@ -46,11 +233,25 @@ macro_rules! generate_slippy_tiles_settings {
                 Self {
                     // Download defaults
                     endpoint: "https://tile.openstreetmap.org".into(),
+                    tiling_scheme: TilingScheme::WebMercator,
                     tiles_directory: PathBuf::from("tiles/"),
                     max_concurrent_downloads: 4,
                     max_retries: 3,
+                    connect_timeout: Some(Duration::from_secs(10)),
+                    request_timeout: Some(Duration::from_secs(30)),
+                    stalled_download_timeout: Some(Duration::from_secs(300)),
+                    failed_tile_retry_delay: Duration::from_secs(60),
+                    proxy: None,
                     rate_limit_requests: 10,
                     rate_limit_window: Duration::from_secs(1),
+                    rate_limit_policy: None,
+                    host_limits: HashMap::new(),
+                    clear_status_on_source_change: true,
+                    cache_index: false,
+                    user_agent: DEFAULT_USER_AGENT.into(),
+                    osm_usage_policy: true,
+                    offline: false,
+                    validation: TileValidation::default(),
 
                     // Other defaults
                     $(
@@ -0,0 +1,88 @@
+use bevy::prelude::Resource;
+use bevy_platform::collections::HashMap;
+use std::{collections::VecDeque, time::Duration};
+
+use crate::types::TileDownloadErrorKind;
+
+/// Number of most recent tile fetches used for the latency percentiles.
+pub const LATENCY_WINDOW: usize = 256;
+
+/// Network statistics of a single tile download task.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TileFetchStats {
+    /// Number of times the request was retried after a failure.
+    pub retries: u32,
+    /// Response bytes received over every attempt.
+    pub bytes: u64,
+    /// Duration of the last attempt, from sending the request to receiving the whole response.
+    pub latency: Option<Duration>,
+}
+
+/// Download statistics, updated by the download systems.
+#[derive(Resource, Debug, Clone, Default)]
+pub struct SlippyTileStats {
+    /// Tile downloads sent to the tile server, by the rate limiter or by jobs.
+    ///
+    /// Requests served from the tiles directory or merged into a download already in flight are not counted.
+    pub requests_issued: u64,
+    /// Requested tiles served from the tiles directory.
+    pub cache_hits: u64,
+    /// Tiles fetched from the network (successfully or not).
+    pub network_fetches: u64,
+    pub bytes_downloaded: u64,
+    /// Failed tiles, by kind of failure.
+    pub failures: HashMap<TileDownloadErrorKind, u64>,
+    pub retries: u64,
+    /// Tile requests currently waiting on the [`DownloadRateLimiter`](crate::DownloadRateLimiter), all hosts combined.
+    pub queue_length: usize,
+    /// Tile downloads currently in flight, by the rate limiter or by jobs.
+    pub in_flight: usize,
+    latencies: VecDeque<Duration>,
+}
+
+impl SlippyTileStats {
+    pub fn new() -> SlippyTileStats {
+        SlippyTileStats::default()
+    }
+
+    /// Total number of failed tiles, whatever the kind of failure.
+    pub fn total_failures(&self) -> u64 {
+        self.failures.values().sum()
+    }
+
+    /// Latency below which `percentile` (0.0 to 100.0) of the last [`LATENCY_WINDOW`] fetches completed.
+    pub fn latency_percentile(&self, percentile: f64) -> Option<Duration> {
+        if self.latencies.is_empty() {
+            return None;
+        }
+        let mut latencies = self.latencies.iter().copied().collect::<Vec<_>>();
+        latencies.sort_unstable();
+        // Nearest-rank percentile.
+        let rank = (percentile.clamp(0.0, 100.0) / 100.0 * latencies.len() as f64).ceil() as usize;
+        Some(latencies[rank.saturating_sub(1)])
+    }
+
+    pub fn latency_p50(&self) -> Option<Duration> {
+        self.latency_percentile(50.0)
+    }
+
+    pub fn latency_p95(&self) -> Option<Duration> {
+        self.latency_percentile(95.0)
+    }
+
+    pub(crate) fn record_fetch(&mut self, stats: &TileFetchStats) {
+        self.network_fetches += 1;
+        self.bytes_downloaded += stats.bytes;
+        self.retries += stats.retries as u64;
+        if let Some(latency) = stats.latency {
+            if self.latencies.len() == LATENCY_WINDOW {
+                self.latencies.pop_front();
+            }
+            self.latencies.push_back(latency);
+        }
+    }
+
+    pub(crate) fn record_failure(&mut self, kind: TileDownloadErrorKind) {
+        *self.failures.entry(kind).or_default() += 1;
+    }
+}
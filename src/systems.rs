@@ -4,63 +4,395 @@ use bevy::{
         io::{AssetReaderError, AssetSourceId},
         AssetServer, AsyncWriteExt as _,
     },
-    prelude::{debug, warn, Commands, MessageReader, MessageWriter, Res, ResMut, Resource},
-    tasks::{futures_lite::future, IoTaskPool, Task},
+    prelude::{
+        debug, warn, DetectChanges, Local, MessageReader, MessageWriter, Res, ResMut, Resource,
+    },
+    tasks::{
+        futures_lite::{future, StreamExt as _},
+        IoTaskPool, Task,
+    },
+};
+use bevy_platform::collections::{HashMap, HashSet};
+use std::{
+    collections::VecDeque,
+    path::Path,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex, PoisonError,
+    },
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
 };
-use std::{collections::VecDeque, path::Path, sync::Arc, time::Instant};
 
 use crate::{
-    AlreadyDownloaded, Coordinates, DownloadSlippyTilesMessage, DownloadStatus, FileExists,
-    SlippyTileCoordinates, SlippyTileDownloadStatus, SlippyTileDownloadTaskKey,
-    SlippyTileDownloadTaskResult, SlippyTileDownloadTasks, SlippyTileDownloadedMessage,
-    SlippyTilesSettings, TileDownloadStatus, TileSize, UseCache, ZoomLevel,
+    fetch, Coordinates, DownloadSlippyTileBoundsMessage, DownloadSlippyTileRegionMessage,
+    DownloadSlippyTilesMessage, DownloadStatus, FileExists, HostLimits, ProxySettings,
+    RateLimitPolicy, SlippyTileCacheIndex, SlippyTileCoordinates, SlippyTileDownloadFailedMessage,
+    SlippyTileDownloadStatus, SlippyTileDownloadTaskKey, SlippyTileDownloadTaskResult,
+    SlippyTileDownloadTasks, SlippyTileDownloadedMessage, SlippyTileFetcher, SlippyTileJobs,
+    SlippyTileRequestHook, SlippyTileStats, SlippyTileValidator, SlippyTilesSettings,
+    TileDownloadError, TileDownloadErrorKind, TileDownloadStatus, TileFetchStats, TileFetcher,
+    TileRequest, TileRequestHook, TileResponse, TileSize, TileValidation, TileValidationError,
+    TileValidator, UseCache, ZoomLevel, DEFAULT_USER_AGENT, OSM_MAX_BULK_TILES,
+    OSM_MAX_BULK_ZOOM_LEVEL, OSM_MIN_CACHE_TTL,
 };
 
 #[derive(Debug)]
-struct BufferedRequest {
-    coords: (u32, u32),
-    zoom_level: ZoomLevel,
-    tile_size: TileSize,
-    endpoint: String,
-    filename: String,
+pub(crate) struct BufferedRequest {
+    pub(crate) coords: (u32, u32),
+    pub(crate) zoom_level: ZoomLevel,
+    pub(crate) tile_size: TileSize,
+    pub(crate) endpoint: String,
+    pub(crate) filename: String,
+}
+
+impl BufferedRequest {
+    fn key(&self) -> SlippyTileDownloadTaskKey {
+        SlippyTileDownloadTaskKey {
+            slippy_tile_coordinates: SlippyTileCoordinates {
+                x: self.coords.0,
+                y: self.coords.1,
+            },
+            zoom_level: self.zoom_level,
+            tile_size: self.tile_size,
+        }
+    }
 }
 
+/// When the current attempt of a tile download got its download slot, `None` while it waits on one - shared between
+/// the download task and [`fail_stalled_downloads`].
+#[derive(Clone, Default)]
+pub(crate) struct FetchClock(Arc<Mutex<Option<Instant>>>);
+
+impl FetchClock {
+    fn set(&self, started: Option<Instant>) {
+        *self.0.lock().unwrap_or_else(PoisonError::into_inner) = started;
+    }
+
+    fn started(&self) -> Option<Instant> {
+        *self.0.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+struct CacheLookup {
+    request: BufferedRequest,
+    task: Task<bool>,
+}
+
+/// Cache existence checks running on the IO task pool, keyed by tile.
 #[derive(Resource, Default)]
-pub struct DownloadRateLimiter {
-    requests: VecDeque<Instant>,
+pub struct CacheLookupTasks(HashMap<SlippyTileDownloadTaskKey, CacheLookup>);
+
+/// Rate limiting state for a single host, following its [`RateLimitPolicy`].
+#[derive(Debug)]
+pub(crate) enum RateLimitState {
+    SlidingWindow {
+        requests: VecDeque<Instant>,
+        max_requests: usize,
+        window: Duration,
+    },
+    TokenBucket {
+        tokens: f64,
+        last_refill: Instant,
+        burst: usize,
+        per_second: f64,
+    },
+}
+
+impl RateLimitState {
+    pub(crate) fn new(policy: &RateLimitPolicy, now: Instant) -> Self {
+        match *policy {
+            RateLimitPolicy::SlidingWindow { requests, window } => RateLimitState::SlidingWindow {
+                requests: VecDeque::new(),
+                max_requests: requests,
+                window,
+            },
+            RateLimitPolicy::TokenBucket { burst, per_second } => RateLimitState::TokenBucket {
+                tokens: burst as f64,
+                last_refill: now,
+                burst,
+                per_second,
+            },
+        }
+    }
+
+    /// Records a request at `now` if the policy allows it. Returns false if the request has to wait.
+    pub(crate) fn try_acquire(&mut self, now: Instant) -> bool {
+        match self {
+            RateLimitState::SlidingWindow {
+                requests,
+                max_requests,
+                window,
+            } => {
+                // Remove old requests outside the window
+                while let Some(time) = requests.front() {
+                    if now.duration_since(*time) > *window {
+                        requests.pop_front();
+                    } else {
+                        break;
+                    }
+                }
+
+                // Check if we can make a new request
+                if requests.len() < *max_requests {
+                    requests.push_back(now);
+                    true
+                } else {
+                    false
+                }
+            },
+            RateLimitState::TokenBucket {
+                tokens,
+                last_refill,
+                burst,
+                per_second,
+            } => {
+                let elapsed = now.saturating_duration_since(*last_refill).as_secs_f64();
+                *tokens = (*tokens + elapsed * *per_second).min(*burst as f64);
+                *last_refill = now;
+
+                if *tokens >= 1.0 {
+                    *tokens -= 1.0;
+                    true
+                } else {
+                    false
+                }
+            },
+        }
+    }
+}
+
+/// Download queue, concurrency limit and rate limit of a single tile host.
+struct HostLimiter {
+    limits: HostLimits,
+    rate_limit: RateLimitState,
+    semaphore: Arc<Semaphore>,
+    /// Download slots still to be taken back from the semaphore after lowering `max_concurrent_downloads`.
+    owed_permits: Arc<AtomicUsize>,
     buffered_requests: VecDeque<BufferedRequest>,
 }
 
+impl HostLimiter {
+    fn new(limits: HostLimits) -> Self {
+        Self {
+            rate_limit: RateLimitState::new(&limits.rate_limit_policy, Instant::now()),
+            semaphore: Arc::new(Semaphore::new(limits.max_concurrent_downloads)),
+            owed_permits: Arc::default(),
+            buffered_requests: VecDeque::new(),
+            limits,
+        }
+    }
+
+    fn set_limits(&mut self, limits: HostLimits) {
+        if limits.rate_limit_policy != self.limits.rate_limit_policy {
+            self.rate_limit = RateLimitState::new(&limits.rate_limit_policy, Instant::now());
+        }
+        let (current, target) = (
+            self.limits.max_concurrent_downloads,
+            limits.max_concurrent_downloads,
+        );
+        if target > current {
+            // Cancel the slots still owed from an earlier decrease before adding new ones.
+            let owed = self
+                .owed_permits
+                .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |owed| {
+                    Some(owed.saturating_sub(target - current))
+                })
+                .unwrap_or_default();
+            self.semaphore
+                .add_permits(target - current - owed.min(target - current));
+        } else if target < current {
+            // Permits held by in-flight downloads can't be revoked, so take the excess ones back as they are released.
+            self.owed_permits
+                .fetch_add(current - target, Ordering::SeqCst);
+            let semaphore = Arc::clone(&self.semaphore);
+            let owed_permits = Arc::clone(&self.owed_permits);
+            IoTaskPool::get()
+                .spawn(async move {
+                    while owed_permits.load(Ordering::SeqCst) > 0 {
+                        let permit = semaphore.acquire_arc().await;
+                        let owed =
+                            owed_permits.fetch_update(Ordering::SeqCst, Ordering::SeqCst, |owed| {
+                                owed.checked_sub(1)
+                            });
+                        if owed.is_err() {
+                            // Nothing owed anymore, the limit was raised again meanwhile.
+                            break;
+                        }
+                        permit.forget();
+                    }
+                })
+                .detach();
+        }
+        self.limits = limits;
+    }
+}
+
+/// Queues tile requests per host and releases them according to each host's [`HostLimits`].
+#[derive(Resource, Default)]
+pub struct DownloadRateLimiter {
+    hosts: HashMap<String, HostLimiter>,
+    /// Tiles that are either buffered or currently downloading - each tile is requested at most once at any moment.
+    pending: HashSet<SlippyTileDownloadTaskKey>,
+    /// Fetch clocks of the tiles released by the rate limiter.
+    fetch_clocks: HashMap<SlippyTileDownloadTaskKey, FetchClock>,
+    /// Tiles downloaded by a [`crate::SlippyTileJob`], with the tile request made for it in the meantime, if any.
+    job_downloads: HashMap<SlippyTileDownloadTaskKey, Option<BufferedRequest>>,
+    /// Tile requests whose job download completed, resolved like cache lookups.
+    completed_job_requests: Vec<(BufferedRequest, FileExists)>,
+}
+
 impl DownloadRateLimiter {
-    fn can_make_request(&mut self, now: Instant, settings: &SlippyTilesSettings) -> bool {
-        // Remove old requests outside the window
-        while let Some(time) = self.requests.front() {
-            if now.duration_since(*time) > settings.rate_limit_window {
-                self.requests.pop_front();
-            } else {
-                break;
+    /// Number of tile requests waiting on the rate limit of the given host.
+    pub fn queue_depth(&self, host: &str) -> usize {
+        self.hosts
+            .get(host)
+            .map_or(0, |host_limiter| host_limiter.buffered_requests.len())
+    }
+
+    /// Number of tile requests waiting on the rate limit, per host.
+    pub fn queue_depths(&self) -> impl Iterator<Item = (&str, usize)> {
+        self.hosts
+            .iter()
+            .map(|(host, host_limiter)| (host.as_str(), host_limiter.buffered_requests.len()))
+    }
+
+    /// Number of tile requests waiting on the rate limit, all hosts combined.
+    pub fn queue_length(&self) -> usize {
+        self.hosts
+            .values()
+            .map(|host_limiter| host_limiter.buffered_requests.len())
+            .sum()
+    }
+
+    /// Number of tile downloads released by the rate limiter or started by a job that haven't completed yet.
+    pub fn in_flight(&self) -> usize {
+        self.pending.len().saturating_sub(self.queue_length())
+    }
+
+    /// Buffers a tile request unless that tile is already queued or in flight.
+    /// Returns true if the request was buffered.
+    pub(crate) fn buffer(
+        &mut self,
+        request: BufferedRequest,
+        settings: &SlippyTilesSettings,
+    ) -> bool {
+        if !self.pending.insert(request.key()) {
+            // The job downloading the tile hands it over once done.
+            if let Some(waiting @ None) = self.job_downloads.get_mut(&request.key()) {
+                *waiting = Some(request);
             }
+            return false;
         }
+        let host = endpoint_host(&request.endpoint);
+        self.hosts
+            .entry(host.to_string())
+            .or_insert_with(|| HostLimiter::new(settings.limits_for_host(host)))
+            .buffered_requests
+            .push_back(request);
+        true
+    }
+
+    /// Whether the tile is queued or in flight.
+    pub(crate) fn is_pending(&self, key: &SlippyTileDownloadTaskKey) -> bool {
+        self.pending.contains(key)
+    }
+
+    /// Takes a download slot from the rate limit of `host` for a background download (see [`crate::SlippyTileJob`]),
+    /// only if no tile request is waiting on it. Returns the host's download semaphore.
+    pub(crate) fn try_acquire_idle(
+        &mut self,
+        host: &str,
+        settings: &SlippyTilesSettings,
+    ) -> Option<Arc<Semaphore>> {
+        let host_limiter = self
+            .hosts
+            .entry(host.to_string())
+            .or_insert_with(|| HostLimiter::new(settings.limits_for_host(host)));
+        (host_limiter.buffered_requests.is_empty()
+            && host_limiter.rate_limit.try_acquire(Instant::now()))
+        .then(|| Arc::clone(&host_limiter.semaphore))
+    }
 
-        // Check if we can make a new request
-        self.requests.len() < settings.rate_limit_requests
+    /// Tracks a tile downloaded by a [`crate::SlippyTileJob`] like a released tile request, so it isn't requested
+    /// twice and stalls are detected. Returns the fetch clock of the download.
+    pub(crate) fn start_job_download(&mut self, key: SlippyTileDownloadTaskKey) -> FetchClock {
+        let fetch_clock = FetchClock::default();
+        self.pending.insert(key.clone());
+        self.fetch_clocks.insert(key.clone(), fetch_clock.clone());
+        self.job_downloads.insert(key, None);
+        fetch_clock
     }
 
-    fn buffer_request(
+    /// Ends a job download, handing the tile over to any tile request made for it in the meantime.
+    pub(crate) fn complete_job_download(
         &mut self,
-        coords: (u32, u32),
-        zoom_level: ZoomLevel,
-        tile_size: TileSize,
-        endpoint: String,
-        filename: String,
+        key: &SlippyTileDownloadTaskKey,
+        downloaded: FileExists,
     ) {
-        self.buffered_requests.push_back(BufferedRequest {
-            coords,
-            zoom_level,
-            tile_size,
-            endpoint,
-            filename,
-        });
+        if let Some(Some(request)) = self.job_downloads.remove(key) {
+            self.completed_job_requests.push((request, downloaded));
+        }
+        self.complete_request(key);
+    }
+
+    /// Job downloads that got their download slot longer than `timeout` ago.
+    fn stalled_job_downloads(
+        &self,
+        now: Instant,
+        timeout: Duration,
+    ) -> Vec<SlippyTileDownloadTaskKey> {
+        self.job_downloads
+            .keys()
+            .filter(|key| {
+                self.fetch_started(key)
+                    .is_some_and(|started| now.duration_since(started) > timeout)
+            })
+            .cloned()
+            .collect()
+    }
+
+    fn complete_request(&mut self, key: &SlippyTileDownloadTaskKey) {
+        self.pending.remove(key);
+        self.fetch_clocks.remove(key);
+    }
+
+    /// When the current download attempt of a released tile got its download slot, if it has one.
+    pub(crate) fn fetch_started(&self, key: &SlippyTileDownloadTaskKey) -> Option<Instant> {
+        self.fetch_clocks.get(key)?.started()
+    }
+
+    /// Applies the current concurrency and rate limits to every known host.
+    fn apply_settings(&mut self, settings: &SlippyTilesSettings) {
+        for (host, host_limiter) in self.hosts.iter_mut() {
+            host_limiter.set_limits(settings.limits_for_host(host));
+        }
+    }
+
+    /// Removes every queued tile request, without releasing it.
+    pub(crate) fn drain_buffered(&mut self) -> Vec<BufferedRequest> {
+        let mut drained = Vec::new();
+        for host_limiter in self.hosts.values_mut() {
+            drained.extend(host_limiter.buffered_requests.drain(..));
+        }
+        for request in &drained {
+            self.pending.remove(&request.key());
+        }
+        drained
+    }
+
+    /// Drops every queued and in-flight tile request. Job downloads are left to their job.
+    fn clear(&mut self) {
+        for host_limiter in self.hosts.values_mut() {
+            host_limiter.buffered_requests.clear();
+        }
+        let job_downloads = &mut self.job_downloads;
+        self.pending.retain(|key| job_downloads.contains_key(key));
+        self.fetch_clocks
+            .retain(|key, _| job_downloads.contains_key(key));
+        job_downloads
+            .values_mut()
+            .for_each(|waiting| *waiting = None);
+        self.completed_job_requests.clear();
     }
 
     fn process_buffered_requests(
@@ -68,16 +400,29 @@ impl DownloadRateLimiter {
         slippy_tile_download_tasks: &mut ResMut<SlippyTileDownloadTasks>,
         slippy_tile_download_status: &mut ResMut<SlippyTileDownloadStatus>,
         asset_server: &AssetServer,
-        download_semaphore: &DownloadSemaphore,
         settings: &SlippyTilesSettings,
+        fetch_settings: &FetchSettings,
+        stats: &mut SlippyTileStats,
     ) {
+        if settings.offline {
+            // Queued requests are failed by `fail_offline_requests` instead.
+            return;
+        }
         let now = Instant::now();
-        while self.can_make_request(now, settings) {
-            if let Some(request) = self.buffered_requests.pop_front() {
+        for host_limiter in self.hosts.values_mut() {
+            while !host_limiter.buffered_requests.is_empty()
+                && host_limiter.rate_limit.try_acquire(now)
+            {
+                let Some(request) = host_limiter.buffered_requests.pop_front() else {
+                    break;
+                };
                 let spc = SlippyTileCoordinates {
                     x: request.coords.0,
                     y: request.coords.1,
                 };
+                let fetch_clock = FetchClock::default();
+                self.fetch_clocks.insert(request.key(), fetch_clock.clone());
+                stats.requests_issued += 1;
 
                 download_and_track_slippy_tile(
                     spc,
@@ -88,140 +433,261 @@ impl DownloadRateLimiter {
                     slippy_tile_download_tasks,
                     slippy_tile_download_status,
                     asset_server,
-                    download_semaphore,
-                    settings,
+                    Arc::clone(&host_limiter.semaphore),
+                    fetch_clock,
+                    fetch_settings.clone(),
                 );
-
-                self.requests.push_back(now);
-            } else {
-                break;
             }
         }
     }
 }
 
-#[derive(Resource)]
-pub struct DownloadSemaphore(Arc<Semaphore>);
-
-impl DownloadSemaphore {
-    fn new(n: usize) -> Self {
-        Self(Arc::new(Semaphore::new(n)))
-    }
-
-    fn semaphore(&self) -> Arc<Semaphore> {
-        Arc::clone(&self.0)
-    }
+/// Returns the host (and port, if any) of a tile endpoint - tile requests are limited per host.
+pub(crate) fn endpoint_host(endpoint: &str) -> &str {
+    let without_scheme = endpoint
+        .split_once("://")
+        .map_or(endpoint, |(_, rest)| rest);
+    let authority = without_scheme
+        .split(['/', '?', '#'])
+        .next()
+        .unwrap_or(without_scheme);
+    // Strip any user info.
+    authority
+        .rsplit_once('@')
+        .map_or(authority, |(_, host)| host)
 }
 
-pub(crate) fn initialize_semaphore(
-    mut commands: Commands,
-    slippy_tiles_settings: Res<SlippyTilesSettings>,
-) {
-    let semaphore = DownloadSemaphore::new(slippy_tiles_settings.max_concurrent_downloads);
-    commands.insert_resource(semaphore);
+/// A single tile out of a download message.
+struct RequestedTile {
+    coords: SlippyTileCoordinates,
+    zoom_level: ZoomLevel,
+    tile_size: TileSize,
+    use_cache: bool,
 }
 
 /// System that listens for DownloadSlippyTiles messages and submits individual tile requests in separate threads.
+#[allow(clippy::too_many_arguments)]
 pub fn download_slippy_tiles(
     mut download_slippy_tile_messages: MessageReader<DownloadSlippyTilesMessage>,
+    mut download_slippy_tile_bounds_messages: MessageReader<DownloadSlippyTileBoundsMessage>,
+    mut download_slippy_tile_region_messages: MessageReader<DownloadSlippyTileRegionMessage>,
     slippy_tiles_settings: Res<SlippyTilesSettings>,
     mut slippy_tile_download_status: ResMut<SlippyTileDownloadStatus>,
     mut slippy_tile_download_tasks: ResMut<SlippyTileDownloadTasks>,
     mut rate_limiter: ResMut<DownloadRateLimiter>,
-    download_semaphore: Res<DownloadSemaphore>,
+    mut cache_lookups: ResMut<CacheLookupTasks>,
+    cache_index: Res<SlippyTileCacheIndex>,
     asset_server: Res<AssetServer>,
+    fetcher: Res<SlippyTileFetcher>,
+    request_hook: Res<SlippyTileRequestHook>,
+    validator: Res<SlippyTileValidator>,
+    mut stats: ResMut<SlippyTileStats>,
 ) {
     // First process any buffered requests
     rate_limiter.process_buffered_requests(
         &mut slippy_tile_download_tasks,
         &mut slippy_tile_download_status,
         &asset_server,
-        &download_semaphore,
         &slippy_tiles_settings,
+        &FetchSettings::new(&slippy_tiles_settings, &fetcher, &request_hook, &validator),
+        &mut stats,
     );
+    for (request, downloaded) in std::mem::take(&mut rate_limiter.completed_job_requests) {
+        resolve_cache_lookup(
+            request,
+            downloaded,
+            &asset_server,
+            &mut rate_limiter,
+            &mut slippy_tile_download_tasks,
+            &mut slippy_tile_download_status,
+            &slippy_tiles_settings,
+        );
+    }
 
+    let mut requested_tiles = Vec::new();
     for download_slippy_tile in download_slippy_tile_messages.read() {
-        let radius = download_slippy_tile.radius.0;
-        let slippy_tile_coords = download_slippy_tile.get_slippy_tile_coordinates();
-
-        // Calculate tile range with overflow protection
-        let min_x = slippy_tile_coords.x.saturating_sub(radius as u32);
-        let min_y = slippy_tile_coords.y.saturating_sub(radius as u32);
-        let max_x = slippy_tile_coords.x.saturating_add(radius as u32);
-        let max_y = slippy_tile_coords.y.saturating_add(radius as u32);
-
-        for x in min_x..=max_x {
-            for y in min_y..=max_y {
-                let spc = SlippyTileCoordinates { x, y };
-                let tiles_directory = slippy_tiles_settings.get_tiles_directory_string();
-                let filename = get_tile_filename(
-                    tiles_directory,
-                    download_slippy_tile.zoom_level,
-                    x,
-                    y,
-                    download_slippy_tile.tile_size,
+        // Wraps around the antimeridian, stops at the top and bottom of the map.
+        requested_tiles.extend(
+            download_slippy_tile
+                .tiles(slippy_tiles_settings.tiling_scheme)
+                .into_iter()
+                .map(|coords| RequestedTile {
+                    coords,
+                    zoom_level: download_slippy_tile.zoom_level,
+                    tile_size: download_slippy_tile.tile_size,
+                    use_cache: download_slippy_tile.use_cache,
+                }),
+        );
+    }
+    let enforces_osm_usage_policy = slippy_tiles_settings
+        .enforces_osm_usage_policy(endpoint_host(&slippy_tiles_settings.endpoint));
+    for download_slippy_tile_bounds in download_slippy_tile_bounds_messages.read() {
+        let tiles = download_slippy_tile_bounds.tiles(slippy_tiles_settings.tiling_scheme);
+        if enforces_osm_usage_policy
+            && is_osm_bulk_download(download_slippy_tile_bounds.zoom_level, tiles.len() as u64)
+        {
+            warn!(
+                "Refusing to download {} OpenStreetMap tiles at zoom level {}, bulk downloads above zoom level {} break the tile usage policy",
+                tiles.len(),
+                download_slippy_tile_bounds.zoom_level.to_u8(),
+                OSM_MAX_BULK_ZOOM_LEVEL.to_u8()
+            );
+            continue;
+        }
+        requested_tiles.extend(tiles.into_iter().map(|coords| RequestedTile {
+            coords,
+            zoom_level: download_slippy_tile_bounds.zoom_level,
+            tile_size: download_slippy_tile_bounds.tile_size,
+            use_cache: download_slippy_tile_bounds.use_cache,
+        }));
+    }
+    for download_slippy_tile_region in download_slippy_tile_region_messages.read() {
+        let tiling_scheme = slippy_tiles_settings.tiling_scheme;
+        if enforces_osm_usage_policy {
+            let bulk_tiles = download_slippy_tile_region
+                .zoom_levels()
+                .filter(|zoom_level| zoom_level.to_u8() > OSM_MAX_BULK_ZOOM_LEVEL.to_u8())
+                .map(|zoom_level| {
+                    download_slippy_tile_region
+                        .region
+                        .tile_count(zoom_level, tiling_scheme)
+                })
+                .sum::<u64>();
+            if is_osm_bulk_download(download_slippy_tile_region.max_zoom_level, bulk_tiles) {
+                warn!(
+                    "Refusing to download {} OpenStreetMap tiles above zoom level {} for a region, bulk downloads break the tile usage policy",
+                    bulk_tiles,
+                    OSM_MAX_BULK_ZOOM_LEVEL.to_u8()
                 );
+                continue;
+            }
+        }
+        for zoom_level in download_slippy_tile_region.zoom_levels() {
+            requested_tiles.extend(
+                download_slippy_tile_region
+                    .region
+                    .tiles(zoom_level, tiling_scheme)
+                    .into_iter()
+                    .map(|coords| RequestedTile {
+                        coords,
+                        zoom_level,
+                        tile_size: download_slippy_tile_region.tile_size,
+                        use_cache: download_slippy_tile_region.use_cache,
+                    }),
+            );
+        }
+    }
 
-                let already_downloaded = slippy_tile_download_status.contains_key_with_coords(
-                    spc,
-                    download_slippy_tile.zoom_level,
-                    download_slippy_tile.tile_size,
-                );
+    for requested_tile in requested_tiles {
+        let SlippyTileCoordinates { x, y } = requested_tile.coords;
+        let tiles_directory = slippy_tiles_settings.get_tiles_directory_string();
+        let request = BufferedRequest {
+            coords: (x, y),
+            zoom_level: requested_tile.zoom_level,
+            tile_size: requested_tile.tile_size,
+            endpoint: slippy_tiles_settings.endpoint.clone(),
+            filename: get_tile_filename(
+                tiles_directory,
+                requested_tile.zoom_level,
+                x,
+                y,
+                requested_tile.tile_size,
+            ),
+        };
 
-                let file_exists = async_file_exists(&asset_server, &filename);
-
-                match (
-                    UseCache::new(download_slippy_tile.use_cache),
-                    AlreadyDownloaded::new(already_downloaded),
-                    FileExists::new(file_exists),
-                ) {
-                    // This should only match when waiting on a file download.
-                    (_, AlreadyDownloaded::Yes, FileExists::No) => {
-                        // Check if the download has timed out
-                        if let Some(status) = slippy_tile_download_status.0.get(&SlippyTileDownloadTaskKey {
-                            slippy_tile_coordinates: spc,
-                            zoom_level: download_slippy_tile.zoom_level,
-                            tile_size: download_slippy_tile.tile_size,
-                        }) {
-                            if matches!(status.load_status, DownloadStatus::Downloading) {
-                                rate_limiter.buffer_request(
-                                    (x, y),
-                                    download_slippy_tile.zoom_level,
-                                    download_slippy_tile.tile_size,
-                                    slippy_tiles_settings.endpoint.clone(),
-                                    filename,
-                                );
-                            }
-                        }
-                    }
-                    // Cache can not be used,
-                    (UseCache::No, _, _)
-                    // OR not downloading yet and no file exists on disk.
-                    | (UseCache::Yes, AlreadyDownloaded::No, FileExists::No) => {
-                        rate_limiter.buffer_request(
-                            (x, y),
-                            download_slippy_tile.zoom_level,
-                            download_slippy_tile.tile_size,
-                            slippy_tiles_settings.endpoint.clone(),
-                            filename,
-                        );
-                    }
-                    // Cache can be used and we have the file on disk.
-                    (UseCache::Yes, _, FileExists::Yes) => load_and_track_slippy_tile_from_disk(
-                        spc,
-                        download_slippy_tile.zoom_level,
-                        download_slippy_tile.tile_size,
-                        filename,
-                        &mut slippy_tile_download_tasks,
-                        &mut slippy_tile_download_status,
-                    ),
-                }
-            }
+        let status = slippy_tile_download_status.0.get(&request.key());
+        match tile_request_action(
+            status,
+            UseCache::new(requested_tile.use_cache),
+            &slippy_tiles_settings,
+            enforces_osm_usage_policy,
+        ) {
+            TileRequestAction::Skip => {},
+            TileRequestAction::Buffer => {
+                rate_limiter.buffer(request, &slippy_tiles_settings);
+            },
+            TileRequestAction::LookupCache => lookup_cached_slippy_tile(
+                request,
+                &cache_index,
+                &mut cache_lookups,
+                &asset_server,
+                &mut rate_limiter,
+                &mut slippy_tile_download_tasks,
+                &mut slippy_tile_download_status,
+                &slippy_tiles_settings,
+            ),
         }
     }
 }
 
-fn get_tile_filename(
+/// Whether requesting `tiles` tiles above [`OSM_MAX_BULK_ZOOM_LEVEL`] at once is a bulk download, forbidden by the
+/// OpenStreetMap tile usage policy.
+fn is_osm_bulk_download(zoom_level: ZoomLevel, tiles: u64) -> bool {
+    zoom_level.to_u8() > OSM_MAX_BULK_ZOOM_LEVEL.to_u8() && tiles > OSM_MAX_BULK_TILES
+}
+
+/// What [`download_slippy_tiles`] does with a requested tile.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum TileRequestAction {
+    /// Nothing, the tile already failed.
+    Skip,
+    /// Queue a download (ignored by the rate limiter if the tile is already queued or in flight).
+    Buffer,
+    /// Check whether we have the file on disk without blocking this frame, downloading it if not.
+    LookupCache,
+}
+
+/// Decides what to do with a requested tile given its current status, if any.
+///
+/// Tiles the tile server can't serve are only requested again when the cache is bypassed, so repeating a request
+/// doesn't hammer the tile server. Tiles that failed for a transient reason are requested again after
+/// `failed_tile_retry_delay`, so a short outage doesn't leave holes in the map.
+pub(crate) fn tile_request_action(
+    status: Option<&TileDownloadStatus>,
+    use_cache: UseCache,
+    settings: &SlippyTilesSettings,
+    enforces_osm_usage_policy: bool,
+) -> TileRequestAction {
+    let load_status = status.map(|status| &status.load_status);
+    // Offline, the cache is all there is.
+    let mut use_cache = matches!(use_cache, UseCache::Yes) || settings.offline;
+    match load_status {
+        // Already known to be missing from the cache, it is requeued once back online.
+        Some(DownloadStatus::Failed(TileDownloadError::Offline)) if settings.offline => {
+            return TileRequestAction::Skip;
+        },
+        // Transient failures are retried after a delay, permanent ones only when the cache is bypassed.
+        Some(DownloadStatus::Failed(error))
+            if use_cache
+                && (error.is_permanent()
+                    || status.is_some_and(|status| {
+                        status.since.elapsed() < settings.failed_tile_retry_delay
+                    })) =>
+        {
+            return TileRequestAction::Skip;
+        },
+        _ => {},
+    }
+    if !use_cache && enforces_osm_usage_policy {
+        // Only refresh tiles known to be older than the minimum cache TTL.
+        use_cache = !status.is_some_and(|status| {
+            matches!(status.load_status, DownloadStatus::Downloaded)
+                && status.since.elapsed() >= OSM_MIN_CACHE_TTL
+        });
+    }
+
+    let downloading = matches!(load_status, Some(DownloadStatus::Downloading));
+    match (UseCache::new(use_cache), downloading) {
+        // Cache can not be used,
+        (UseCache::No, _)
+        // OR still waiting on a file download.
+        | (UseCache::Yes, true) => TileRequestAction::Buffer,
+        // Cache can be used.
+        (UseCache::Yes, false) => TileRequestAction::LookupCache,
+    }
+}
+
+pub(crate) fn get_tile_filename(
     tiles_directory: String,
     zoom_level: ZoomLevel,
     x: u32,
@@ -238,17 +704,296 @@ fn get_tile_filename(
     )
 }
 
-fn async_file_exists(asset_server: &AssetServer, filename: &str) -> bool {
-    let asset_source = match asset_server.get_source(AssetSourceId::Default) {
-        Ok(source) => source,
-        Err(_) => return false,
+pub(crate) fn parse_tile_filename(filename: &str) -> Option<SlippyTileDownloadTaskKey> {
+    let mut parts = filename.strip_suffix(".tile.png")?.split('.');
+    let zoom_level = ZoomLevel::try_from(parts.next()?.parse::<u8>().ok()?).ok()?;
+    let x = parts.next()?.parse().ok()?;
+    let y = parts.next()?.parse().ok()?;
+    let tile_size = TileSize::try_from(parts.next()?.parse::<u32>().ok()?).ok()?;
+    if parts.next().is_some() {
+        return None;
+    }
+    Some(SlippyTileDownloadTaskKey {
+        slippy_tile_coordinates: SlippyTileCoordinates { x, y },
+        zoom_level,
+        tile_size,
+    })
+}
+
+#[allow(clippy::too_many_arguments)]
+fn lookup_cached_slippy_tile(
+    request: BufferedRequest,
+    cache_index: &SlippyTileCacheIndex,
+    cache_lookups: &mut CacheLookupTasks,
+    asset_server: &AssetServer,
+    rate_limiter: &mut DownloadRateLimiter,
+    slippy_tile_download_tasks: &mut ResMut<SlippyTileDownloadTasks>,
+    slippy_tile_download_status: &mut ResMut<SlippyTileDownloadStatus>,
+    settings: &SlippyTilesSettings,
+) {
+    let key = request.key();
+    if cache_index.is_ready() {
+        let file_exists = FileExists::new(cache_index.contains(&key));
+        resolve_cache_lookup(
+            request,
+            file_exists,
+            asset_server,
+            rate_limiter,
+            slippy_tile_download_tasks,
+            slippy_tile_download_status,
+            settings,
+        );
+    } else if !cache_lookups.0.contains_key(&key) {
+        let task = spawn_cache_lookup_task(request.filename.clone(), asset_server);
+        cache_lookups.0.insert(key, CacheLookup { request, task });
+    }
+}
+
+fn resolve_cache_lookup(
+    request: BufferedRequest,
+    file_exists: FileExists,
+    asset_server: &AssetServer,
+    rate_limiter: &mut DownloadRateLimiter,
+    slippy_tile_download_tasks: &mut ResMut<SlippyTileDownloadTasks>,
+    slippy_tile_download_status: &mut ResMut<SlippyTileDownloadStatus>,
+    settings: &SlippyTilesSettings,
+) {
+    match file_exists {
+        FileExists::Yes => load_and_track_slippy_tile_from_disk(
+            SlippyTileCoordinates {
+                x: request.coords.0,
+                y: request.coords.1,
+            },
+            request.zoom_level,
+            request.tile_size,
+            request.filename,
+            asset_server,
+            slippy_tile_download_tasks,
+            slippy_tile_download_status,
+        ),
+        FileExists::No => {
+            rate_limiter.buffer(request, settings);
+        },
+    }
+}
+
+pub(crate) fn spawn_cache_lookup_task(filename: String, asset_server: &AssetServer) -> Task<bool> {
+    let thread_pool = IoTaskPool::get();
+    let asset_server = asset_server.clone();
+    thread_pool.spawn(async move {
+        let asset_source = match asset_server.get_source(AssetSourceId::Default) {
+            Ok(source) => source,
+            Err(_) => return false,
+        };
+
+        let asset_reader = asset_source.reader();
+        match asset_reader.read(Path::new(&filename)).await {
+            Ok(_) => true,
+            Err(AssetReaderError::NotFound(_)) => false,
+            Err(_) => false,
+        }
+    })
+}
+
+fn spawn_cache_index_task(
+    tiles_directory: String,
+    asset_server: &AssetServer,
+) -> Task<HashSet<SlippyTileDownloadTaskKey>> {
+    let thread_pool = IoTaskPool::get();
+    let asset_server = asset_server.clone();
+    thread_pool.spawn(async move {
+        let mut keys = HashSet::new();
+        let asset_source = match asset_server.get_source(AssetSourceId::Default) {
+            Ok(source) => source,
+            Err(_) => return keys,
+        };
+
+        let asset_reader = asset_source.reader();
+        let mut paths = match asset_reader
+            .read_directory(Path::new(&tiles_directory))
+            .await
+        {
+            Ok(paths) => paths,
+            Err(AssetReaderError::NotFound(_)) => return keys,
+            Err(e) => {
+                warn!("Failed to index tiles directory: {:?}", e);
+                return keys;
+            },
+        };
+
+        while let Some(path) = paths.next().await {
+            if let Some(key) = path
+                .file_name()
+                .and_then(|filename| filename.to_str())
+                .and_then(parse_tile_filename)
+            {
+                keys.insert(key);
+            }
+        }
+        debug!("Indexed {} cached slippy tiles", keys.len());
+        keys
+    })
+}
+
+pub(crate) fn initialize_cache_index(
+    slippy_tiles_settings: Res<SlippyTilesSettings>,
+    mut cache_index: ResMut<SlippyTileCacheIndex>,
+    asset_server: Res<AssetServer>,
+) {
+    if slippy_tiles_settings.cache_index {
+        cache_index.task = Some(spawn_cache_index_task(
+            slippy_tiles_settings.get_tiles_directory_string(),
+            &asset_server,
+        ));
+    }
+}
+
+/// System that warns about tile usage policy violations that can't be enforced.
+pub fn check_osm_usage_policy(slippy_tiles_settings: Res<SlippyTilesSettings>) {
+    if slippy_tiles_settings
+        .enforces_osm_usage_policy(endpoint_host(&slippy_tiles_settings.endpoint))
+        && slippy_tiles_settings.user_agent == DEFAULT_USER_AGENT
+    {
+        warn!(
+            "!!! Downloading OpenStreetMap tiles with the default bevy_slippy_tiles User-Agent. \
+            The OpenStreetMap tile usage policy requires a User-Agent identifying your application, \
+            set `SlippyTilesSettings::user_agent` or your requests may be blocked. \
+            See https://operations.osmfoundation.org/policies/tiles/ !!!"
+        );
+    }
+}
+
+/// System that applies runtime changes to [`SlippyTilesSettings`].
+///
+/// Concurrency and rate limits are updated for every host. When `endpoint`, `tiling_scheme` or `tiles_directory` change and
+/// `clear_status_on_source_change` is set, pending work and the in-memory download status are cleared so tiles get requested again.
+#[allow(clippy::too_many_arguments)]
+pub fn apply_settings_changes(
+    slippy_tiles_settings: Res<SlippyTilesSettings>,
+    mut previous_settings: Local<Option<SlippyTilesSettings>>,
+    mut rate_limiter: ResMut<DownloadRateLimiter>,
+    mut slippy_tile_download_status: ResMut<SlippyTileDownloadStatus>,
+    mut slippy_tile_download_tasks: ResMut<SlippyTileDownloadTasks>,
+    mut cache_lookups: ResMut<CacheLookupTasks>,
+    mut cache_index: ResMut<SlippyTileCacheIndex>,
+    asset_server: Res<AssetServer>,
+) {
+    if !slippy_tiles_settings.is_changed() {
+        return;
+    }
+    let Some(previous) = previous_settings.replace(slippy_tiles_settings.clone()) else {
+        // First run, nothing to compare against.
+        return;
+    };
+
+    rate_limiter.apply_settings(&slippy_tiles_settings);
+
+    let endpoint_changed = previous.endpoint != slippy_tiles_settings.endpoint
+        || previous.tiling_scheme != slippy_tiles_settings.tiling_scheme;
+    let tiles_directory_changed = previous.tiles_directory != slippy_tiles_settings.tiles_directory;
+    if (endpoint_changed || tiles_directory_changed)
+        && slippy_tiles_settings.clear_status_on_source_change
+    {
+        debug!("Tile source changed, clearing slippy tile download status");
+        rate_limiter.clear();
+        // Dropping the tasks cancels them.
+        slippy_tile_download_tasks.0.clear();
+        slippy_tile_download_status.0.clear();
+        cache_lookups.0.clear();
+    }
+
+    if previous.offline && !slippy_tiles_settings.offline {
+        requeue_offline_tiles(
+            &mut rate_limiter,
+            &slippy_tile_download_status,
+            &slippy_tiles_settings,
+        );
+    }
+
+    if tiles_directory_changed || previous.cache_index != slippy_tiles_settings.cache_index {
+        *cache_index = SlippyTileCacheIndex::new();
+        if slippy_tiles_settings.cache_index {
+            cache_index.task = Some(spawn_cache_index_task(
+                slippy_tiles_settings.get_tiles_directory_string(),
+                &asset_server,
+            ));
+        }
+    }
+}
+
+/// Queues the download of every tile that was unavailable while offline.
+pub(crate) fn requeue_offline_tiles(
+    rate_limiter: &mut DownloadRateLimiter,
+    slippy_tile_download_status: &SlippyTileDownloadStatus,
+    settings: &SlippyTilesSettings,
+) {
+    for (key, status) in slippy_tile_download_status.0.iter() {
+        if !matches!(
+            status.load_status,
+            DownloadStatus::Failed(TileDownloadError::Offline)
+        ) {
+            continue;
+        }
+        let SlippyTileCoordinates { x, y } = key.slippy_tile_coordinates;
+        rate_limiter.buffer(
+            BufferedRequest {
+                coords: (x, y),
+                zoom_level: key.zoom_level,
+                tile_size: key.tile_size,
+                endpoint: settings.endpoint.clone(),
+                filename: get_tile_filename(
+                    settings.get_tiles_directory_string(),
+                    key.zoom_level,
+                    x,
+                    y,
+                    key.tile_size,
+                ),
+            },
+            settings,
+        );
+    }
+}
+
+/// System that completes the in-memory cache index once the tiles directory has been scanned.
+pub fn update_cache_index(mut cache_index: ResMut<SlippyTileCacheIndex>) {
+    let Some(task) = cache_index.task.as_mut() else {
+        return;
     };
+    if let Some(keys) = future::block_on(future::poll_once(task)) {
+        // Keep any tiles that finished downloading while the directory was being scanned.
+        cache_index.keys.extend(keys);
+        cache_index.task = None;
+        cache_index.ready = true;
+    }
+}
 
-    let asset_reader = asset_source.reader();
-    match future::block_on(asset_reader.read(Path::new(filename))) {
-        Ok(_) => true,
-        Err(AssetReaderError::NotFound(_)) => false,
-        Err(_) => false,
+/// System that resolves finished cache lookups - cached tiles are loaded from disk, the rest are queued for download.
+pub fn process_cache_lookups(
+    mut cache_lookups: ResMut<CacheLookupTasks>,
+    mut rate_limiter: ResMut<DownloadRateLimiter>,
+    mut slippy_tile_download_tasks: ResMut<SlippyTileDownloadTasks>,
+    mut slippy_tile_download_status: ResMut<SlippyTileDownloadStatus>,
+    slippy_tiles_settings: Res<SlippyTilesSettings>,
+    asset_server: Res<AssetServer>,
+) {
+    let mut completed = Vec::new();
+    for (key, lookup) in cache_lookups.0.iter_mut() {
+        if let Some(file_exists) = future::block_on(future::poll_once(&mut lookup.task)) {
+            completed.push((key.clone(), file_exists));
+        }
+    }
+    for (key, file_exists) in completed {
+        if let Some(CacheLookup { request, .. }) = cache_lookups.0.remove(&key) {
+            resolve_cache_lookup(
+                request,
+                FileExists::new(file_exists),
+                &asset_server,
+                &mut rate_limiter,
+                &mut slippy_tile_download_tasks,
+                &mut slippy_tile_download_status,
+                &slippy_tiles_settings,
+            );
+        }
     }
 }
 
@@ -262,8 +1007,9 @@ fn download_and_track_slippy_tile(
     slippy_tile_download_tasks: &mut ResMut<SlippyTileDownloadTasks>,
     slippy_tile_download_status: &mut ResMut<SlippyTileDownloadStatus>,
     asset_server: &AssetServer,
-    download_semaphore: &DownloadSemaphore,
-    settings: &SlippyTilesSettings,
+    semaphore: Arc<Semaphore>,
+    fetch_clock: FetchClock,
+    fetch_settings: FetchSettings,
 ) {
     let task = download_slippy_tile(
         spc,
@@ -272,8 +1018,9 @@ fn download_and_track_slippy_tile(
         endpoint,
         filename.clone(),
         asset_server,
-        download_semaphore,
-        settings.max_retries,
+        semaphore,
+        fetch_clock,
+        fetch_settings,
     );
 
     slippy_tile_download_tasks.insert(spc.x, spc.y, zoom_level, tile_size, task);
@@ -287,15 +1034,16 @@ fn download_and_track_slippy_tile(
 }
 
 #[allow(clippy::too_many_arguments)]
-fn download_slippy_tile(
+pub(crate) fn download_slippy_tile(
     spc: SlippyTileCoordinates,
     zoom_level: ZoomLevel,
     tile_size: TileSize,
     endpoint: String,
     filename: String,
     asset_server: &AssetServer,
-    download_semaphore: &DownloadSemaphore,
-    max_retries: u32,
+    semaphore: Arc<Semaphore>,
+    fetch_clock: FetchClock,
+    fetch_settings: FetchSettings,
 ) -> Task<SlippyTileDownloadTaskResult> {
     debug!(
         "Fetching map tile at position {:?} with zoom level {:?} from {:?}",
@@ -304,10 +1052,12 @@ fn download_slippy_tile(
     let tile_url = get_tile_url(endpoint, tile_size, zoom_level, spc.x, spc.y);
     spawn_slippy_tile_download_task(
         tile_url,
+        tile_size,
         filename,
         asset_server,
-        download_semaphore,
-        max_retries,
+        semaphore,
+        fetch_clock,
+        fetch_settings,
     )
 }
 
@@ -328,111 +1078,267 @@ fn get_tile_url(
     )
 }
 
+/// The subset of [`SlippyTilesSettings`] used by tile download tasks, along with the [`TileFetcher`],
+/// [`TileRequestHook`] and [`TileValidator`] to use.
+#[derive(Clone)]
+pub(crate) struct FetchSettings {
+    fetcher: Arc<dyn TileFetcher>,
+    request_hook: Option<Arc<dyn TileRequestHook>>,
+    validator: Option<Arc<dyn TileValidator>>,
+    validation: TileValidation,
+    max_retries: u32,
+    connect_timeout: Option<Duration>,
+    request_timeout: Option<Duration>,
+    proxy: Option<ProxySettings>,
+    user_agent: String,
+}
+
+impl FetchSettings {
+    pub(crate) fn new(
+        settings: &SlippyTilesSettings,
+        fetcher: &SlippyTileFetcher,
+        request_hook: &SlippyTileRequestHook,
+        validator: &SlippyTileValidator,
+    ) -> Self {
+        Self {
+            fetcher: Arc::clone(&fetcher.0),
+            request_hook: request_hook.0.clone(),
+            validator: validator.0.clone(),
+            validation: settings.validation.clone(),
+            max_retries: settings.max_retries,
+            connect_timeout: settings.connect_timeout,
+            request_timeout: settings.request_timeout,
+            proxy: settings.proxy.clone(),
+            user_agent: settings.user_agent.clone(),
+        }
+    }
+}
+
 fn spawn_slippy_tile_download_task(
     tile_url: String,
+    tile_size: TileSize,
     filename: String,
     asset_server: &AssetServer,
-    download_semaphore: &DownloadSemaphore,
-    max_retries: u32,
+    semaphore: Arc<Semaphore>,
+    fetch_clock: FetchClock,
+    fetch_settings: FetchSettings,
 ) -> Task<SlippyTileDownloadTaskResult> {
     let thread_pool = IoTaskPool::get();
     let asset_server = asset_server.clone();
-    let semaphore = download_semaphore.semaphore();
 
     thread_pool.spawn(async move {
         let mut retries = 0;
+        let mut fetch_stats = TileFetchStats::default();
         let result = loop {
-            if retries >= max_retries {
+            let error = match fetch_and_store_slippy_tile(
+                &tile_url,
+                tile_size,
+                &filename,
+                &asset_server,
+                &semaphore,
+                &fetch_clock,
+                &fetch_settings,
+                &mut fetch_stats,
+            )
+            .await
+            {
+                Ok(downloaded_at) => break Ok(downloaded_at),
+                Err(e) => e,
+            };
+
+            warn!("Tile download error for {}: {}", tile_url, error);
+            if matches!(error, TileDownloadError::Invalid(_)) {
+                // The tile server would most likely send the same tile again.
+                break Err(error);
+            }
+            retries += 1;
+            if retries >= fetch_settings.max_retries {
                 warn!("Max retries reached for tile download: {}", tile_url);
-                break Err("Max retries reached".to_string());
+                break Err(error);
             }
+            fetch_stats.retries += 1;
+        };
 
-            let request = ehttp::Request {
-                method: "GET".to_owned(),
-                url: tile_url.clone(),
-                body: vec![],
-                headers: ehttp::Headers::new(&[
-                    ("User-Agent", "bevy_slippy_tiles/0.7.0 (https://github.com/edouardpoitras/bevy_slippy_tiles)"),
-                    ("Accept", "image/png"),
-                ]),
-            };
+        SlippyTileDownloadTaskResult {
+            path: Path::new(&filename).to_path_buf(),
+            downloaded_at: result.as_ref().ok().copied(),
+            result: result.map(|_| ()),
+            fetch_stats: Some(fetch_stats),
+        }
+    })
+}
 
-            let result = {
-                let _guard = semaphore.acquire().await;
-                ehttp::fetch_async(request).await
-            };
-            match result {
-                Ok(response) => {
-                    if response.status == 200 {
-                        let asset_source = asset_server.get_source(AssetSourceId::Default).unwrap();
-                        let asset_writer = match asset_source.writer() {
-                            Ok(writer) => writer,
-                            Err(e) => {
-                                warn!("Failed to get asset writer: {:?}", e);
-                                retries += 1;
-                                continue;
-                            }
-                        };
-
-                        let mut writer = match asset_writer.write(Path::new(&filename)).await {
-                            Ok(writer) => writer,
-                            Err(e) => {
-                                warn!("Failed to create file writer: {:?}", e);
-                                retries += 1;
-                                continue;
-                            }
-                        };
-
-                        if let Err(e) = writer.write_all(&response.bytes).await {
-                            warn!("Failed to write tile data: {:?}", e);
-                            retries += 1;
-                            continue;
-                        }
+#[allow(clippy::too_many_arguments)]
+async fn fetch_and_store_slippy_tile(
+    tile_url: &str,
+    tile_size: TileSize,
+    filename: &str,
+    asset_server: &AssetServer,
+    semaphore: &Semaphore,
+    fetch_clock: &FetchClock,
+    fetch_settings: &FetchSettings,
+    fetch_stats: &mut TileFetchStats,
+) -> Result<SystemTime, TileDownloadError> {
+    let request = TileRequest {
+        url: tile_url.to_owned(),
+        headers: vec![
+            ("User-Agent".into(), fetch_settings.user_agent.clone()),
+            ("Accept".into(), "image/png".into()),
+        ],
+        connect_timeout: fetch_settings.connect_timeout,
+        request_timeout: fetch_settings.request_timeout,
+        proxy: fetch_settings
+            .proxy
+            .clone()
+            .filter(|proxy| !proxy.bypasses(endpoint_host(tile_url))),
+    };
 
-                        if let Err(e) = writer.close().await {
-                            warn!("Failed to close file writer: {:?}", e);
-                            retries += 1;
-                            continue;
-                        }
+    let response = {
+        let _guard = semaphore.acquire().await;
+        // Only time spent with a download slot counts towards `stalled_download_timeout`.
+        fetch_clock.set(Some(Instant::now()));
+        let response = fetch_slippy_tile(request, fetch_settings, fetch_stats).await;
+        fetch_clock.set(None);
+        response?
+    };
+    if response.status != 200 {
+        return Err(TileDownloadError::Http(response.status));
+    }
+    validate_slippy_tile(&response, tile_size, fetch_settings)
+        .map_err(TileDownloadError::Invalid)?;
 
-                        break Ok(());
-                    } else {
-                        warn!("HTTP error {}: {}", response.status, response.status_text);
-                        retries += 1;
-                        continue;
-                    }
-                }
-                Err(e) => {
-                    warn!("Download error: {:?}", e);
-                    retries += 1;
-                    continue;
-                }
-            }
+    let asset_source = asset_server
+        .get_source(AssetSourceId::Default)
+        .map_err(|e| TileDownloadError::Io(e.to_string()))?;
+    let asset_writer = asset_source
+        .writer()
+        .map_err(|e| TileDownloadError::Io(format!("Failed to get asset writer: {:?}", e)))?;
+    let mut writer = asset_writer
+        .write(Path::new(filename))
+        .await
+        .map_err(|e| TileDownloadError::Io(format!("Failed to create file writer: {:?}", e)))?;
+    writer
+        .write_all(&response.bytes)
+        .await
+        .map_err(|e| TileDownloadError::Io(format!("Failed to write tile data: {:?}", e)))?;
+    writer
+        .close()
+        .await
+        .map_err(|e| TileDownloadError::Io(format!("Failed to close file writer: {:?}", e)))?;
+
+    let downloaded_at = SystemTime::now();
+    if let Err(e) = write_download_time(asset_server, filename, downloaded_at).await {
+        warn!("Failed to record the download time of {}: {}", filename, e);
+    }
+    Ok(downloaded_at)
+}
+
+/// File next to a cached tile recording when it was downloaded, so cache TTLs survive restarts.
+fn download_time_filename(filename: &str) -> String {
+    format!("{}.downloaded_at", filename)
+}
+
+async fn write_download_time(
+    asset_server: &AssetServer,
+    filename: &str,
+    downloaded_at: SystemTime,
+) -> Result<(), String> {
+    let seconds = downloaded_at
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let asset_source = asset_server
+        .get_source(AssetSourceId::Default)
+        .map_err(|e| e.to_string())?;
+    let asset_writer = asset_source.writer().map_err(|e| format!("{:?}", e))?;
+    let mut writer = asset_writer
+        .write(Path::new(&download_time_filename(filename)))
+        .await
+        .map_err(|e| format!("{:?}", e))?;
+    writer
+        .write_all(seconds.to_string().as_bytes())
+        .await
+        .map_err(|e| e.to_string())?;
+    writer.close().await.map_err(|e| e.to_string())
+}
+
+/// When a cached tile was downloaded, if recorded.
+async fn read_download_time(asset_server: &AssetServer, filename: &str) -> Option<SystemTime> {
+    let asset_source = asset_server.get_source(AssetSourceId::Default).ok()?;
+    let path = download_time_filename(filename);
+    let mut reader = asset_source.reader().read(Path::new(&path)).await.ok()?;
+    let mut data = Vec::new();
+    reader.read_to_end(&mut data).await.ok()?;
+    let seconds = std::str::from_utf8(&data).ok()?.trim().parse().ok()?;
+    Some(UNIX_EPOCH + Duration::from_secs(seconds))
+}
+
+/// Checks a downloaded tile against the [`TileValidation`] settings, then the [`TileValidator`] if any.
+pub(crate) fn validate_slippy_tile(
+    response: &TileResponse,
+    tile_size: TileSize,
+    fetch_settings: &FetchSettings,
+) -> Result<(), TileValidationError> {
+    fetch_settings.validation.validate(response, tile_size)?;
+    match &fetch_settings.validator {
+        Some(validator) => validator
+            .validate(response)
+            .map_err(TileValidationError::Rejected),
+        None => Ok(()),
+    }
+}
+
+/// Sends a tile request through the request hook, if any, preparing and sending it once more if the hook refreshed its
+/// credentials after a 401 Unauthorized response.
+pub(crate) async fn fetch_slippy_tile(
+    request: TileRequest,
+    fetch_settings: &FetchSettings,
+    fetch_stats: &mut TileFetchStats,
+) -> Result<TileResponse, TileDownloadError> {
+    let mut refreshed = false;
+    loop {
+        let prepared = match &fetch_settings.request_hook {
+            Some(hook) => hook.prepare(request.clone()).await?,
+            None => request.clone(),
         };
+        let started = Instant::now();
+        // Enforce the request timeout here as well, in case the fetcher doesn't.
+        let response = fetch::with_timeout(
+            fetch_settings.fetcher.fetch(prepared),
+            fetch_settings.request_timeout,
+        )
+        .await
+        .ok_or(TileDownloadError::RequestTimeout)??;
+        fetch_stats.latency = Some(started.elapsed());
+        fetch_stats.bytes += response.bytes.len() as u64;
 
-        match result {
-            Ok(()) => SlippyTileDownloadTaskResult {
-                path: Path::new(&filename).to_path_buf(),
-            },
-            Err(e) => {
-                warn!("Failed to download tile: {}", e);
-                SlippyTileDownloadTaskResult {
-                    path: Path::new(&filename).to_path_buf(),
+        match &fetch_settings.request_hook {
+            Some(hook) if response.status == 401 && !refreshed => {
+                if !hook.refresh(&response).await {
+                    return Ok(response);
                 }
-            }
+                debug!(
+                    "Retrying tile request with refreshed credentials: {}",
+                    request.url
+                );
+                refreshed = true;
+            },
+            _ => return Ok(response),
         }
-    })
+    }
 }
 
+#[allow(clippy::too_many_arguments)]
 fn load_and_track_slippy_tile_from_disk(
     spc: SlippyTileCoordinates,
     zoom_level: ZoomLevel,
     tile_size: TileSize,
     filename: String,
+    asset_server: &AssetServer,
     slippy_tile_download_tasks: &mut ResMut<SlippyTileDownloadTasks>,
     slippy_tile_download_status: &mut ResMut<SlippyTileDownloadStatus>,
 ) {
-    let task = load_slippy_tile_from_disk(filename.clone());
+    let task = load_slippy_tile_from_disk(filename.clone(), asset_server);
     slippy_tile_download_tasks.insert_with_coords(spc, zoom_level, tile_size, task);
     slippy_tile_download_status.insert_with_coords(
         spc,
@@ -443,50 +1349,99 @@ fn load_and_track_slippy_tile_from_disk(
     );
 }
 
-fn load_slippy_tile_from_disk(filename: String) -> Task<SlippyTileDownloadTaskResult> {
+fn load_slippy_tile_from_disk(
+    filename: String,
+    asset_server: &AssetServer,
+) -> Task<SlippyTileDownloadTaskResult> {
     debug!("Loading slippy tile from disk - {}", filename);
-    spawn_fake_slippy_tile_download_task(filename)
-}
-
-fn spawn_fake_slippy_tile_download_task(filename: String) -> Task<SlippyTileDownloadTaskResult> {
     let thread_pool = IoTaskPool::get();
+    let asset_server = asset_server.clone();
     thread_pool.spawn(async move {
+        let downloaded_at = match read_download_time(&asset_server, &filename).await {
+            Some(downloaded_at) => downloaded_at,
+            None => {
+                // Cached before download times were recorded, its age counts from now on.
+                let now = SystemTime::now();
+                if let Err(e) = write_download_time(&asset_server, &filename, now).await {
+                    debug!("Failed to record the download time of {}: {}", filename, e);
+                }
+                now
+            },
+        };
         SlippyTileDownloadTaskResult {
             path: Path::new(&filename).to_path_buf(),
+            result: Ok(()),
+            fetch_stats: None,
+            downloaded_at: Some(downloaded_at),
         }
     })
 }
 
-/// System that checks for completed slippy tile downloads and notifies via a SlippyTileDownloadedMessage message.
+/// System that checks for completed slippy tile downloads and notifies via a SlippyTileDownloadedMessage message,
+/// or a SlippyTileDownloadFailedMessage message if the tile could not be retrieved.
 pub fn download_slippy_tiles_completed(
     mut slippy_tile_download_status: ResMut<SlippyTileDownloadStatus>,
     mut slippy_tile_download_tasks: ResMut<SlippyTileDownloadTasks>,
     mut slippy_tile_downloaded_messages: MessageWriter<SlippyTileDownloadedMessage>,
+    mut slippy_tile_download_failed_messages: MessageWriter<SlippyTileDownloadFailedMessage>,
+    mut rate_limiter: ResMut<DownloadRateLimiter>,
+    mut cache_index: ResMut<SlippyTileCacheIndex>,
+    mut stats: ResMut<SlippyTileStats>,
 ) {
     let mut to_be_removed: Vec<SlippyTileDownloadTaskKey> = Vec::new();
     for (stdtk, task) in slippy_tile_download_tasks.0.iter_mut() {
-        if let Some(SlippyTileDownloadTaskResult { path }) =
-            future::block_on(future::poll_once(task))
+        if let Some(SlippyTileDownloadTaskResult {
+            path,
+            result,
+            fetch_stats,
+            downloaded_at,
+        }) = future::block_on(future::poll_once(task))
         {
-            debug!("Done fetching map tile: {:?}", path);
-            // Add to our map tiles.
-            slippy_tile_download_status.0.insert(
-                stdtk.clone(),
-                TileDownloadStatus {
-                    path: path.clone(),
-                    load_status: DownloadStatus::Downloaded,
+            match fetch_stats {
+                Some(fetch_stats) => stats.record_fetch(&fetch_stats),
+                None => stats.cache_hits += 1,
+            }
+            match result {
+                Ok(()) => {
+                    debug!("Done fetching map tile: {:?}", path);
+                    // Add to our map tiles, aged from their download so cache TTLs carry over from earlier runs.
+                    let age = downloaded_at.and_then(|downloaded_at| downloaded_at.elapsed().ok());
+                    slippy_tile_download_status.0.insert(
+                        stdtk.clone(),
+                        TileDownloadStatus {
+                            path: path.clone(),
+                            load_status: DownloadStatus::Downloaded,
+                            since: age
+                                .and_then(|age| Instant::now().checked_sub(age))
+                                .unwrap_or_else(Instant::now),
+                        },
+                    );
+                    cache_index.insert(stdtk.clone());
+                    // Notify any message consumers.
+                    slippy_tile_downloaded_messages.write(SlippyTileDownloadedMessage {
+                        zoom_level: stdtk.zoom_level,
+                        tile_size: stdtk.tile_size,
+                        coordinates: Coordinates::from_slippy_tile_coordinates(
+                            stdtk.slippy_tile_coordinates.x,
+                            stdtk.slippy_tile_coordinates.y,
+                        ),
+                        path: path.clone(),
+                    });
                 },
-            );
-            // Notify any message consumers.
-            slippy_tile_downloaded_messages.write(SlippyTileDownloadedMessage {
-                zoom_level: stdtk.zoom_level,
-                tile_size: stdtk.tile_size,
-                coordinates: Coordinates::from_slippy_tile_coordinates(
-                    stdtk.slippy_tile_coordinates.x,
-                    stdtk.slippy_tile_coordinates.y,
-                ),
-                path: path.clone(),
-            });
+                Err(error) => {
+                    warn!("Failed to download tile {:?}: {}", path, error);
+                    stats.record_failure(error.kind());
+                    slippy_tile_download_status.0.insert(
+                        stdtk.clone(),
+                        TileDownloadStatus {
+                            path: path.clone(),
+                            load_status: DownloadStatus::Failed(error.clone()),
+                            since: Instant::now(),
+                        },
+                    );
+                    slippy_tile_download_failed_messages.write(failed_message(stdtk, error));
+                },
+            }
             // Task is complete, remove entry.
             to_be_removed.push(stdtk.clone());
         }
@@ -494,5 +1449,100 @@ pub fn download_slippy_tiles_completed(
     // Clean up finished handled tasks.
     for remove_key in to_be_removed {
         slippy_tile_download_tasks.0.remove(&remove_key);
+        rate_limiter.complete_request(&remove_key);
+    }
+}
+
+/// System that abandons tiles stuck downloading for longer than `stalled_download_timeout`, marking them as failed.
+/// Stalled job tiles count as failed for their job.
+pub fn fail_stalled_downloads(
+    slippy_tiles_settings: Res<SlippyTilesSettings>,
+    mut slippy_tile_download_status: ResMut<SlippyTileDownloadStatus>,
+    mut slippy_tile_download_tasks: ResMut<SlippyTileDownloadTasks>,
+    mut slippy_tile_download_failed_messages: MessageWriter<SlippyTileDownloadFailedMessage>,
+    mut rate_limiter: ResMut<DownloadRateLimiter>,
+    mut jobs: ResMut<SlippyTileJobs>,
+    mut stats: ResMut<SlippyTileStats>,
+) {
+    let Some(stalled_download_timeout) = slippy_tiles_settings.stalled_download_timeout else {
+        return;
+    };
+    let now = Instant::now();
+    for (stdtk, status) in slippy_tile_download_status.0.iter_mut() {
+        if matches!(status.load_status, DownloadStatus::Downloading)
+            && rate_limiter
+                .fetch_started(stdtk)
+                .is_some_and(|started| now.duration_since(started) > stalled_download_timeout)
+        {
+            warn!("Tile download stalled: {:?}", status.path);
+            // Dropping the task cancels it, releasing its download slot.
+            slippy_tile_download_tasks.0.remove(stdtk);
+            rate_limiter.complete_request(stdtk);
+            status.load_status = DownloadStatus::Failed(TileDownloadError::Stalled);
+            stats.network_fetches += 1;
+            stats.record_failure(TileDownloadErrorKind::Stalled);
+            status.since = now;
+            slippy_tile_download_failed_messages
+                .write(failed_message(stdtk, TileDownloadError::Stalled));
+        }
+    }
+    for stdtk in rate_limiter.stalled_job_downloads(now, stalled_download_timeout) {
+        rate_limiter.complete_job_download(&stdtk, FileExists::No);
+        if jobs.fail_stalled_download(&stdtk) {
+            stats.network_fetches += 1;
+            stats.record_failure(TileDownloadErrorKind::Stalled);
+        }
+    }
+}
+
+/// System that fails every queued tile request with [`TileDownloadError::Offline`] while `offline` mode is enabled.
+pub fn fail_offline_requests(
+    slippy_tiles_settings: Res<SlippyTilesSettings>,
+    mut slippy_tile_download_status: ResMut<SlippyTileDownloadStatus>,
+    mut slippy_tile_download_failed_messages: MessageWriter<SlippyTileDownloadFailedMessage>,
+    mut rate_limiter: ResMut<DownloadRateLimiter>,
+    mut stats: ResMut<SlippyTileStats>,
+) {
+    if !slippy_tiles_settings.offline {
+        return;
+    }
+    for request in rate_limiter.drain_buffered() {
+        let key = request.key();
+        debug!("Tile unavailable offline: {}", request.filename);
+        slippy_tile_download_status.0.insert(
+            key.clone(),
+            TileDownloadStatus {
+                path: Path::new(&request.filename).to_path_buf(),
+                load_status: DownloadStatus::Failed(TileDownloadError::Offline),
+                since: Instant::now(),
+            },
+        );
+        stats.record_failure(TileDownloadErrorKind::Offline);
+        slippy_tile_download_failed_messages
+            .write(failed_message(&key, TileDownloadError::Offline));
+    }
+}
+
+/// System that keeps the queue length and in-flight count of [`SlippyTileStats`] up to date.
+pub fn update_slippy_tile_stats(
+    rate_limiter: Res<DownloadRateLimiter>,
+    mut stats: ResMut<SlippyTileStats>,
+) {
+    stats.queue_length = rate_limiter.queue_length();
+    stats.in_flight = rate_limiter.in_flight();
+}
+
+fn failed_message(
+    stdtk: &SlippyTileDownloadTaskKey,
+    error: TileDownloadError,
+) -> SlippyTileDownloadFailedMessage {
+    SlippyTileDownloadFailedMessage {
+        zoom_level: stdtk.zoom_level,
+        tile_size: stdtk.tile_size,
+        coordinates: Coordinates::from_slippy_tile_coordinates(
+            stdtk.slippy_tile_coordinates.x,
+            stdtk.slippy_tile_coordinates.y,
+        ),
+        error,
     }
 }
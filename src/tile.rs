@@ -0,0 +1,128 @@
+use bevy::prelude::Component;
+
+use crate::{
+    coordinates::{LatLonBounds, SlippyTileCoordinates, SlippyTileRange},
+    tiling::TilingScheme,
+    types::ZoomLevel,
+    web_mercator::{tile_meters, WebMercatorBounds, WEB_MERCATOR_EXTENT},
+};
+
+/// A slippy tile along with its zoom level, identifying it in the tile pyramid.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, Component)]
+pub struct TileId {
+    pub x: u32,
+    pub y: u32,
+    pub zoom_level: ZoomLevel,
+}
+
+impl TileId {
+    pub fn new(x: u32, y: u32, zoom_level: ZoomLevel) -> TileId {
+        TileId { x, y, zoom_level }
+    }
+
+    pub fn from_slippy_tile_coordinates(
+        coords: SlippyTileCoordinates,
+        zoom_level: ZoomLevel,
+    ) -> TileId {
+        TileId::new(coords.x, coords.y, zoom_level)
+    }
+
+    pub fn slippy_tile_coordinates(&self) -> SlippyTileCoordinates {
+        SlippyTileCoordinates {
+            x: self.x,
+            y: self.y,
+        }
+    }
+
+    /// The tile one zoom level up containing this tile, `None` at zoom level 0.
+    pub fn parent(&self) -> Option<TileId> {
+        let zoom_level = ZoomLevel::try_from(self.zoom_level.to_u8().checked_sub(1)?).ok()?;
+        self.ancestor_at(zoom_level)
+    }
+
+    /// The four tiles one zoom level down covering this tile (none at the maximum zoom level), row by row.
+    pub fn children(&self) -> impl Iterator<Item = TileId> {
+        let tile = *self;
+        ZoomLevel::try_from(self.zoom_level.to_u8() + 1)
+            .into_iter()
+            .flat_map(move |zoom_level| tile.descendants_at(zoom_level))
+    }
+
+    /// The tile at the given (lower or equal) zoom level containing this tile, `None` if `zoom_level` is higher.
+    pub fn ancestor_at(&self, zoom_level: ZoomLevel) -> Option<TileId> {
+        let levels = self.zoom_level.to_u8().checked_sub(zoom_level.to_u8())?;
+        Some(TileId::new(self.x >> levels, self.y >> levels, zoom_level))
+    }
+
+    /// The tiles at the given (higher or equal) zoom level covering this tile, row by row.
+    /// There are none if `zoom_level` is lower.
+    pub fn descendants_at(&self, zoom_level: ZoomLevel) -> impl Iterator<Item = TileId> {
+        self.descendant_range(zoom_level)
+            .into_iter()
+            .flat_map(move |range| {
+                range
+                    .iter()
+                    .map(move |coords| TileId::from_slippy_tile_coordinates(coords, zoom_level))
+            })
+    }
+
+    /// The (up to) eight tiles surrounding this tile. Columns wrap around the antimeridian, rows stop at the top and
+    /// bottom edges of the map.
+    pub fn neighbors(&self) -> impl Iterator<Item = TileId> {
+        let tile = *self;
+        SlippyTileRange::around(
+            self.slippy_tile_coordinates(),
+            1,
+            self.zoom_level,
+            TilingScheme::WebMercator,
+        )
+        .iter()
+        .map(move |coords| TileId::from_slippy_tile_coordinates(coords, tile.zoom_level))
+        .filter(move |neighbor| *neighbor != tile)
+    }
+
+    /// Whether the other tile is this tile or one of its descendants.
+    pub fn contains(&self, other: &TileId) -> bool {
+        other.ancestor_at(self.zoom_level) == Some(*self)
+    }
+
+    /// The latitude/longitude bounding box of the tile.
+    pub fn bounds(&self) -> LatLonBounds {
+        LatLonBounds::from_tile_range(&self.range())
+    }
+
+    /// The bounding box of the tile in Web Mercator (EPSG:3857) meters.
+    pub fn web_mercator_bounds(&self) -> WebMercatorBounds {
+        let tile_meters = tile_meters(self.zoom_level);
+        WebMercatorBounds {
+            min_x: -WEB_MERCATOR_EXTENT + self.x as f64 * tile_meters,
+            min_y: WEB_MERCATOR_EXTENT - (self.y + 1) as f64 * tile_meters,
+            max_x: -WEB_MERCATOR_EXTENT + (self.x + 1) as f64 * tile_meters,
+            max_y: WEB_MERCATOR_EXTENT - self.y as f64 * tile_meters,
+        }
+    }
+
+    /// A range containing only this tile.
+    pub fn range(&self) -> SlippyTileRange {
+        SlippyTileRange {
+            x: self.x,
+            y: self.y,
+            width: 1,
+            height: 1,
+            zoom_level: self.zoom_level,
+            tiling_scheme: TilingScheme::WebMercator,
+        }
+    }
+
+    fn descendant_range(&self, zoom_level: ZoomLevel) -> Option<SlippyTileRange> {
+        let levels = zoom_level.to_u8().checked_sub(self.zoom_level.to_u8())?;
+        Some(SlippyTileRange {
+            x: self.x << levels,
+            y: self.y << levels,
+            width: 1 << levels,
+            height: 1 << levels,
+            zoom_level,
+            tiling_scheme: TilingScheme::WebMercator,
+        })
+    }
+}
@@ -0,0 +1,235 @@
+use crate::{
+    coordinates::{
+        latitude_to_tile_y_fraction, Coordinates, FractionalTileCoordinates, LatLonBounds,
+        LatitudeLongitudeCoordinates, SlippyTileCoordinates, SlippyTileRange,
+    },
+    projection::WebMercatorProjection,
+    types::{TileSize, ZoomLevel},
+};
+
+/// How the world is cut into tiles by the tile server.
+///
+/// Tile coordinates count columns from the antimeridian eastward and rows from the north edge of the map southward
+/// in both schemes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum TilingScheme {
+    /// Web Mercator (EPSG:3857) tiles, one tile at zoom level 0 - used by OpenStreetMap and most tile servers.
+    #[default]
+    WebMercator,
+    /// Geographic (EPSG:4326, plate carrée) tiles, two tiles side by side at zoom level 0, each covering 180° of
+    /// longitude and latitude - used by NASA GIBS and some WMTS servers.
+    Geographic,
+}
+
+impl TilingScheme {
+    /// Number of tile columns at the given zoom level.
+    pub fn columns(&self, zoom_level: ZoomLevel) -> u32 {
+        match self {
+            TilingScheme::WebMercator => 1 << zoom_level.to_u8(),
+            TilingScheme::Geographic => 2 << zoom_level.to_u8(),
+        }
+    }
+
+    /// Number of tile rows at the given zoom level.
+    pub fn rows(&self, zoom_level: ZoomLevel) -> u32 {
+        1 << zoom_level.to_u8()
+    }
+
+    /// Fractional tile coordinates of a lat/lon. Longitudes wrap around the antimeridian, latitudes are clamped to
+    /// the edges of the map.
+    pub fn to_fractional_tile_coordinates(
+        &self,
+        coords: &LatitudeLongitudeCoordinates,
+        zoom_level: ZoomLevel,
+    ) -> FractionalTileCoordinates {
+        match self {
+            TilingScheme::WebMercator => coords.to_fractional_tile_coordinates(zoom_level),
+            TilingScheme::Geographic => {
+                let degrees_per_tile = geographic_degrees_per_tile(zoom_level);
+                let columns = self.columns(zoom_level) as f64;
+                FractionalTileCoordinates {
+                    x: ((coords.longitude + 180.0) / degrees_per_tile).rem_euclid(columns),
+                    y: self.latitude_to_tile_y(coords.latitude, zoom_level),
+                }
+            },
+        }
+    }
+
+    /// Fractional tile row of a latitude, clamped to the edges of the map.
+    pub(crate) fn latitude_to_tile_y(&self, latitude: f64, zoom_level: ZoomLevel) -> f64 {
+        match self {
+            TilingScheme::WebMercator => latitude_to_tile_y_fraction(latitude, zoom_level),
+            TilingScheme::Geographic => ((90.0 - latitude)
+                / geographic_degrees_per_tile(zoom_level))
+            .clamp(0.0, self.rows(zoom_level) as f64),
+        }
+    }
+
+    /// Lat/lon of fractional tile coordinates.
+    pub fn fractional_tile_to_latitude_longitude(
+        &self,
+        coords: FractionalTileCoordinates,
+        zoom_level: ZoomLevel,
+    ) -> LatitudeLongitudeCoordinates {
+        match self {
+            TilingScheme::WebMercator => coords.to_latitude_longitude(zoom_level),
+            TilingScheme::Geographic => {
+                let degrees_per_tile = geographic_degrees_per_tile(zoom_level);
+                LatitudeLongitudeCoordinates {
+                    latitude: 90.0 - coords.y * degrees_per_tile,
+                    longitude: coords.x * degrees_per_tile - 180.0,
+                }
+            },
+        }
+    }
+
+    /// The tile containing a lat/lon (points on the southern edge of the map belong to the last row).
+    pub fn tile_at(
+        &self,
+        coords: &LatitudeLongitudeCoordinates,
+        zoom_level: ZoomLevel,
+    ) -> SlippyTileCoordinates {
+        let fractional = self.to_fractional_tile_coordinates(coords, zoom_level);
+        SlippyTileCoordinates {
+            x: (fractional.x as u32).min(self.columns(zoom_level) - 1),
+            y: (fractional.y as u32).min(self.rows(zoom_level) - 1),
+        }
+    }
+
+    /// The tile identified by the coordinates: slippy tile coordinates are used as is.
+    pub fn slippy_tile_coordinates(
+        &self,
+        coords: &Coordinates,
+        zoom_level: ZoomLevel,
+    ) -> SlippyTileCoordinates {
+        match coords {
+            Coordinates::LatitudeLongitude(coords) => self.tile_at(coords, zoom_level),
+            Coordinates::SlippyTile(coords) => *coords,
+        }
+    }
+
+    /// Lat/lon of the north-west corner of a tile.
+    pub fn tile_to_latitude_longitude(
+        &self,
+        coords: SlippyTileCoordinates,
+        zoom_level: ZoomLevel,
+    ) -> LatitudeLongitudeCoordinates {
+        self.fractional_tile_to_latitude_longitude(coords.into(), zoom_level)
+    }
+
+    /// The latitude/longitude bounding box of a tile.
+    pub fn tile_bounds(
+        &self,
+        coords: SlippyTileCoordinates,
+        zoom_level: ZoomLevel,
+    ) -> LatLonBounds {
+        let north_west = self.tile_to_latitude_longitude(coords, zoom_level);
+        let south_east = self.fractional_tile_to_latitude_longitude(
+            FractionalTileCoordinates {
+                x: coords.x as f64 + 1.0,
+                y: coords.y as f64 + 1.0,
+            },
+            zoom_level,
+        );
+        LatLonBounds {
+            north: north_west.latitude,
+            south: south_east.latitude,
+            west: north_west.longitude,
+            east: south_east.longitude,
+        }
+    }
+
+    /// The tiles within `radius` tiles of `center`, row by row from the north-west corner.
+    ///
+    /// Columns wrap around the antimeridian, rows stop at the top and bottom edges of the map.
+    pub fn tiles_around(
+        &self,
+        center: SlippyTileCoordinates,
+        radius: u32,
+        zoom_level: ZoomLevel,
+    ) -> Vec<SlippyTileCoordinates> {
+        SlippyTileRange::around(center, radius, zoom_level, *self)
+            .iter()
+            .collect()
+    }
+
+    /// The tiles between two corner coordinates, inclusive, row by row from the north-west corner.
+    ///
+    /// The range crosses the antimeridian if `north_west` is east of `south_east`. Tiles only touching the east or
+    /// south edge of a latitude/longitude bounding box are not part of it.
+    pub fn tiles_between(
+        &self,
+        north_west: &Coordinates,
+        south_east: &Coordinates,
+        zoom_level: ZoomLevel,
+    ) -> Vec<SlippyTileCoordinates> {
+        SlippyTileRange::from_corners(north_west, south_east, zoom_level, *self)
+            .iter()
+            .collect()
+    }
+
+    /// Width and height of the whole map, in pixels.
+    pub fn world_pixels(&self, tile_size: TileSize, zoom_level: ZoomLevel) -> (f64, f64) {
+        let tile_pixels = tile_size.to_pixels() as f64;
+        (
+            self.columns(zoom_level) as f64 * tile_pixels,
+            self.rows(zoom_level) as f64 * tile_pixels,
+        )
+    }
+
+    /// Given world coordinates, return the x and y pixel position in the world (0,0 at the bottom left), as with
+    /// [`world_coords_to_world_pixel`](crate::world_coords_to_world_pixel) for Web Mercator tiles.
+    pub fn world_coords_to_world_pixel(
+        &self,
+        coords: &LatitudeLongitudeCoordinates,
+        tile_size: TileSize,
+        zoom_level: ZoomLevel,
+    ) -> (f64, f64) {
+        match self {
+            TilingScheme::WebMercator => {
+                let projection = WebMercatorProjection::new(tile_size, zoom_level);
+                let (x, y) = projection.to_pixel(coords);
+                (x, projection.world_pixels() - y)
+            },
+            TilingScheme::Geographic => {
+                let pixels_per_degree = geographic_pixels_per_degree(tile_size, zoom_level);
+                (
+                    (coords.longitude + 180.0) * pixels_per_degree,
+                    (coords.latitude.clamp(-90.0, 90.0) + 90.0) * pixels_per_degree,
+                )
+            },
+        }
+    }
+
+    /// Given a x and y pixel position in the world (0,0 at the bottom left), return the world coordinates.
+    pub fn world_pixel_to_world_coords(
+        &self,
+        x_pixel: f64,
+        y_pixel: f64,
+        tile_size: TileSize,
+        zoom_level: ZoomLevel,
+    ) -> LatitudeLongitudeCoordinates {
+        match self {
+            TilingScheme::WebMercator => {
+                let projection = WebMercatorProjection::new(tile_size, zoom_level);
+                projection.to_latitude_longitude(x_pixel, projection.world_pixels() - y_pixel)
+            },
+            TilingScheme::Geographic => {
+                let pixels_per_degree = geographic_pixels_per_degree(tile_size, zoom_level);
+                LatitudeLongitudeCoordinates {
+                    latitude: y_pixel / pixels_per_degree - 90.0,
+                    longitude: x_pixel / pixels_per_degree - 180.0,
+                }
+            },
+        }
+    }
+}
+
+// Width and height of a geographic tile at the given zoom level, in degrees.
+fn geographic_degrees_per_tile(zoom_level: ZoomLevel) -> f64 {
+    180.0 / (1u64 << zoom_level.to_u8()) as f64
+}
+
+fn geographic_pixels_per_degree(tile_size: TileSize, zoom_level: ZoomLevel) -> f64 {
+    tile_size.to_pixels() as f64 / geographic_degrees_per_tile(zoom_level)
+}
@@ -1,3 +1,5 @@
+use std::num::NonZeroU32;
+
 macro_rules! generate_zoom_level {
     { $( $name:ident => $val:literal, )+ } => {
         /// The zoom level used when fetching tiles (0 <= zoom <= 25)
@@ -13,12 +15,12 @@ macro_rules! generate_zoom_level {
         }
 
         impl TryFrom<u8> for ZoomLevel {
-            type Error = ();
+            type Error = InvalidZoomLevel;
 
             fn try_from(v: u8) -> Result<Self, Self::Error> {
                 match v {
                     $( $val => Ok(Self::$name), )+
-                    _ => Err(()),
+                    _ => Err(InvalidZoomLevel(v)),
                 }
             }
         }
@@ -54,23 +56,90 @@ generate_zoom_level! {
     L25 => 25,
 }
 
-/// The size of the tiles being requested - either 256px (Normal), 512px (Large), or 768px (VeryLarge).
-/// Not every tile provider supports Large and VeryLarge.
+/// A zoom level above the maximum of 25.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InvalidZoomLevel(pub u8);
+
+impl std::fmt::Display for InvalidZoomLevel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "invalid zoom level {} (0 <= zoom <= 25)", self.0)
+    }
+}
+
+impl std::error::Error for InvalidZoomLevel {}
+
+/// Coordinates that can't be placed on the map.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum InvalidCoordinates {
+    /// The latitude is NaN or infinite.
+    Latitude(f64),
+    /// The longitude is NaN or infinite.
+    Longitude(f64),
+}
+
+impl std::fmt::Display for InvalidCoordinates {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            InvalidCoordinates::Latitude(latitude) => write!(f, "invalid latitude {}", latitude),
+            InvalidCoordinates::Longitude(longitude) => {
+                write!(f, "invalid longitude {}", longitude)
+            },
+        }
+    }
+}
+
+impl std::error::Error for InvalidCoordinates {}
+
+/// Text that could not be parsed as coordinates.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseCoordinatesError {
+    /// The text is not in a supported format.
+    Format(String),
+    /// The coordinates are in a supported format, but outside of the valid range (latitude, longitude, UTM zone...).
+    OutOfRange(String),
+}
+
+impl std::fmt::Display for ParseCoordinatesError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseCoordinatesError::Format(text) => write!(f, "unrecognized coordinates {:?}", text),
+            ParseCoordinatesError::OutOfRange(text) => {
+                write!(f, "coordinates out of range {:?}", text)
+            },
+        }
+    }
+}
+
+impl std::error::Error for ParseCoordinatesError {}
+
+/// The size of the tiles being requested - either 256px (Normal), 512px (Large), 768px (VeryLarge), or any other size (Custom).
+/// Not every tile provider supports sizes other than Normal.
 #[derive(Eq, Hash, PartialEq, Clone, Copy, Debug)]
 pub enum TileSize {
     Normal,
     Large,
     VeryLarge,
+    /// Any other tile size (example: 1024px or 384px), created with [`TileSize::new`] or [`TileSize::try_from`].
+    Custom(CustomTileSize),
+}
+
+/// A tile size other than 256px, 512px and 768px, so each size has a single [`TileSize`] value.
+#[derive(Eq, Hash, PartialEq, Clone, Copy, Debug)]
+pub struct CustomTileSize(NonZeroU32);
+
+impl CustomTileSize {
+    pub fn to_pixels(&self) -> u32 {
+        self.0.get()
+    }
 }
 
 impl TileSize {
-    /// Create a new TileSize type given a pixel count (512px = TileSize::Large, every other value is TileSize::Normal).
+    /// Create a new TileSize type given a pixel count (256px = TileSize::Normal, 512px = TileSize::Large,
+    /// 768px = TileSize::VeryLarge, every other value is TileSize::Custom).
+    ///
+    /// Panics if `tile_pixels` is 0, use [`TileSize::try_from`] to handle it.
     pub fn new(tile_pixels: u32) -> TileSize {
-        match tile_pixels {
-            768 => TileSize::VeryLarge,
-            512 => TileSize::Large,
-            _ => TileSize::Normal,
-        }
+        TileSize::try_from(tile_pixels).unwrap_or_else(|error| panic!("{}", error))
     }
 
     /// Returns the number of tile pixels given a TileSize variant.
@@ -79,18 +148,52 @@ impl TileSize {
             TileSize::Normal => 256,
             TileSize::Large => 512,
             TileSize::VeryLarge => 768,
+            TileSize::Custom(tile_size) => tile_size.to_pixels(),
         }
     }
 
+    /// The scale suffix of tile URLs, relative to 256px tiles (example: `@2x` for 512px, `@1.5x` for 384px).
     pub fn get_url_postfix(&self) -> String {
         match self {
             TileSize::Normal => "".into(),
             TileSize::Large => "@2x".into(),
             TileSize::VeryLarge => "@3x".into(),
+            TileSize::Custom(tile_size) => format!("@{}x", tile_size.to_pixels() as f64 / 256.0),
         }
     }
 }
 
+impl TryFrom<u32> for TileSize {
+    type Error = InvalidTileSize;
+
+    fn try_from(tile_pixels: u32) -> Result<Self, Self::Error> {
+        match tile_pixels {
+            256 => Ok(TileSize::Normal),
+            512 => Ok(TileSize::Large),
+            768 => Ok(TileSize::VeryLarge),
+            _ => NonZeroU32::new(tile_pixels)
+                .map(|tile_pixels| TileSize::Custom(CustomTileSize(tile_pixels)))
+                .ok_or(InvalidTileSize(tile_pixels)),
+        }
+    }
+}
+
+/// A tile size of 0 pixels.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InvalidTileSize(pub u32);
+
+impl std::fmt::Display for InvalidTileSize {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "invalid tile size {}px (tiles must be at least 1px)",
+            self.0
+        )
+    }
+}
+
+impl std::error::Error for InvalidTileSize {}
+
 /// Number of tiles away from the main tile that should be fetched. Effectively translates to layers of surrounding tiles. Will degrade performance exponentially.
 ///
 /// Radius(0) = 1 tile (1x1), Radius(1) = 9 tiles (3x3), Radius(2) = 25 tiles (5x5), Radius(3) = 49 tiles (7x7), etc.
@@ -143,4 +246,119 @@ impl FileExists {
 pub enum DownloadStatus {
     Downloading,
     Downloaded,
+    Failed(TileDownloadError),
+}
+
+/// Why a slippy tile could not be retrieved.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TileDownloadError {
+    /// The tile server could not be reached, or sent nothing, within `connect_timeout`.
+    ConnectTimeout,
+    /// The full response was not received within `request_timeout`.
+    RequestTimeout,
+    /// The tile was still downloading after `stalled_download_timeout` and was abandoned.
+    Stalled,
+    /// The tile server responded with a non-200 HTTP status.
+    Http(u16),
+    /// The request failed before a response was received (DNS, TLS, connection reset, etc).
+    Network(String),
+    /// The tile could not be written to the tiles directory.
+    Io(String),
+    /// The tile is not cached and `offline` mode is enabled.
+    Offline,
+    /// The tile server responded, but the tile was rejected by the [`TileValidation`](crate::TileValidation) checks
+    /// or the [`SlippyTileValidator`](crate::SlippyTileValidator).
+    Invalid(TileValidationError),
+}
+
+impl std::fmt::Display for TileDownloadError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TileDownloadError::ConnectTimeout => write!(f, "connection timed out"),
+            TileDownloadError::RequestTimeout => write!(f, "request timed out"),
+            TileDownloadError::Stalled => write!(f, "download stalled"),
+            TileDownloadError::Http(status) => write!(f, "HTTP error {}", status),
+            TileDownloadError::Network(e) => write!(f, "network error: {}", e),
+            TileDownloadError::Io(e) => write!(f, "IO error: {}", e),
+            TileDownloadError::Offline => write!(f, "tile unavailable offline"),
+            TileDownloadError::Invalid(e) => write!(f, "invalid tile: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for TileDownloadError {}
+
+impl TileDownloadError {
+    pub fn kind(&self) -> TileDownloadErrorKind {
+        match self {
+            TileDownloadError::ConnectTimeout => TileDownloadErrorKind::ConnectTimeout,
+            TileDownloadError::RequestTimeout => TileDownloadErrorKind::RequestTimeout,
+            TileDownloadError::Stalled => TileDownloadErrorKind::Stalled,
+            TileDownloadError::Http(_) => TileDownloadErrorKind::Http,
+            TileDownloadError::Network(_) => TileDownloadErrorKind::Network,
+            TileDownloadError::Io(_) => TileDownloadErrorKind::Io,
+            TileDownloadError::Offline => TileDownloadErrorKind::Offline,
+            TileDownloadError::Invalid(_) => TileDownloadErrorKind::Invalid,
+        }
+    }
+
+    /// Whether requesting the tile again would fail the same way: the tile was rejected, or the tile server answered
+    /// with a client error (other than 408 Request Timeout and 429 Too Many Requests).
+    pub fn is_permanent(&self) -> bool {
+        match self {
+            TileDownloadError::Http(status) => {
+                (400..500).contains(status) && ![408, 429].contains(status)
+            },
+            TileDownloadError::Invalid(_) => true,
+            _ => false,
+        }
+    }
+}
+
+/// Why a downloaded tile was rejected before being cached.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TileValidationError {
+    /// The response's `Content-Type` (if any) is not one of the accepted content types.
+    ContentType(Option<String>),
+    /// The response body is smaller than `min_bytes`.
+    TooSmall(usize),
+    /// The response body is larger than `max_bytes`.
+    TooLarge(usize),
+    /// The response body could not be decoded as an image.
+    Undecodable(String),
+    /// The image doesn't have the dimensions of the requested tile size.
+    Dimensions { width: u32, height: u32 },
+    /// The tile was rejected by the [`SlippyTileValidator`](crate::SlippyTileValidator).
+    Rejected(String),
+}
+
+impl std::fmt::Display for TileValidationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TileValidationError::ContentType(Some(content_type)) => {
+                write!(f, "unexpected content type {}", content_type)
+            },
+            TileValidationError::ContentType(None) => write!(f, "missing content type"),
+            TileValidationError::TooSmall(size) => write!(f, "too small ({} bytes)", size),
+            TileValidationError::TooLarge(size) => write!(f, "too large ({} bytes)", size),
+            TileValidationError::Undecodable(e) => write!(f, "undecodable image: {}", e),
+            TileValidationError::Dimensions { width, height } => {
+                write!(f, "unexpected dimensions {}x{}", width, height)
+            },
+            TileValidationError::Rejected(reason) => write!(f, "rejected: {}", reason),
+        }
+    }
+}
+
+/// The kind of a [`TileDownloadError`], without its details.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TileDownloadErrorKind {
+    ConnectTimeout,
+    RequestTimeout,
+    Stalled,
+    Http,
+    Network,
+    Io,
+    Offline,
+    Invalid,
 }
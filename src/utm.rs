@@ -0,0 +1,377 @@
+use std::{fmt, str::FromStr};
+
+use crate::{
+    constants::{EARTH_RADIUS, WGS84_FLATTENING},
+    coordinates::{normalize_longitude, LatitudeLongitudeCoordinates},
+    types::ParseCoordinatesError,
+};
+
+/// Scale factor on the central meridian of UTM zones.
+const UTM_SCALE_FACTOR: f64 = 0.9996;
+const UTM_FALSE_EASTING: f64 = 500_000.0;
+/// Added to southern hemisphere northings, so they are never negative.
+const UTM_FALSE_NORTHING: f64 = 10_000_000.0;
+/// Latitude bands from 80°S to 84°N, 8° high (12° for X).
+const UTM_BANDS: &[u8; 20] = b"CDEFGHJKLMNPQRSTUVWX";
+/// MGRS 100km square column letters, for zones 1, 2 and 3 (repeating every 3 zones).
+const MGRS_COLUMNS: [&[u8; 8]; 3] = [b"ABCDEFGH", b"JKLMNPQR", b"STUVWXYZ"];
+/// MGRS 100km square row letters, offset by 5 letters in even zones.
+const MGRS_ROWS: &[u8; 20] = b"ABCDEFGHJKLMNPQRSTUV";
+const MGRS_SQUARE: f64 = 100_000.0;
+
+/// Universal Transverse Mercator coordinates on the WGS84 ellipsoid, covering latitudes from 80°S to 84°N.
+///
+/// Displayed as `31U 448251 5411932` (zone, latitude band, easting and northing in meters), with the number of
+/// decimals of the meters as the precision (`{:.2}`, none by default).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Utm {
+    /// Longitude zone, from 1 to 60.
+    pub zone: u8,
+    /// Latitude band letter, from `C` to `X` - `N` and above are in the northern hemisphere.
+    pub band: char,
+    pub easting: f64,
+    pub northing: f64,
+}
+
+impl Utm {
+    /// UTM coordinates of a lat/lon, `None` outside of the UTM latitude limits (80°S to 84°N).
+    ///
+    /// The zone exceptions around Norway and Svalbard are applied.
+    pub fn from_latitude_longitude(coords: &LatitudeLongitudeCoordinates) -> Option<Utm> {
+        if !(-80.0..=84.0).contains(&coords.latitude) || !coords.longitude.is_finite() {
+            return None;
+        }
+        let longitude = normalize_longitude(coords.longitude);
+        let band = utm_band(coords.latitude);
+        let mut zone = ((longitude + 180.0) / 6.0).floor() as u8 + 1;
+        if band == 'V' && zone == 31 && longitude >= 3.0 {
+            zone = 32;
+        }
+        if band == 'X' {
+            zone = match longitude {
+                lon if (0.0..9.0).contains(&lon) => 31,
+                lon if (9.0..21.0).contains(&lon) => 33,
+                lon if (21.0..33.0).contains(&lon) => 35,
+                lon if (33.0..42.0).contains(&lon) => 37,
+                _ => zone,
+            };
+        }
+        let (easting, northing) = transverse_mercator(coords.latitude, longitude, zone);
+        Some(Utm {
+            zone,
+            band,
+            easting,
+            northing,
+        })
+    }
+
+    /// Whether the coordinates are in the northern hemisphere (northings start at the equator instead of 10000km
+    /// south of it).
+    pub fn is_northern_hemisphere(&self) -> bool {
+        self.band >= 'N'
+    }
+
+    pub fn to_latitude_longitude(&self) -> LatitudeLongitudeCoordinates {
+        let (n, a) = krueger_n_and_a();
+        let beta = [
+            n / 2.0 - 2.0 / 3.0 * n.powi(2) + 37.0 / 96.0 * n.powi(3) - n.powi(4) / 360.0,
+            n.powi(2) / 48.0 + n.powi(3) / 15.0 - 437.0 / 1440.0 * n.powi(4),
+            17.0 / 480.0 * n.powi(3) - 37.0 / 840.0 * n.powi(4),
+            4397.0 / 161280.0 * n.powi(4),
+        ];
+        let delta = [
+            2.0 * n - 2.0 / 3.0 * n.powi(2) - 2.0 * n.powi(3) + 116.0 / 45.0 * n.powi(4),
+            7.0 / 3.0 * n.powi(2) - 8.0 / 5.0 * n.powi(3) - 227.0 / 45.0 * n.powi(4),
+            56.0 / 15.0 * n.powi(3) - 136.0 / 35.0 * n.powi(4),
+            4279.0 / 630.0 * n.powi(4),
+        ];
+        let false_northing = if self.is_northern_hemisphere() {
+            0.0
+        } else {
+            UTM_FALSE_NORTHING
+        };
+        let xi = (self.northing - false_northing) / (UTM_SCALE_FACTOR * a);
+        let eta = (self.easting - UTM_FALSE_EASTING) / (UTM_SCALE_FACTOR * a);
+        let (mut xi_prime, mut eta_prime) = (xi, eta);
+        for (j, beta) in (1..=4).map(|j| j as f64 * 2.0).zip(beta) {
+            xi_prime -= beta * (j * xi).sin() * (j * eta).cosh();
+            eta_prime -= beta * (j * xi).cos() * (j * eta).sinh();
+        }
+        let chi = (xi_prime.sin() / eta_prime.cosh()).asin();
+        let latitude = chi
+            + (1..=4)
+                .zip(delta)
+                .map(|(j, delta)| delta * (2.0 * j as f64 * chi).sin())
+                .sum::<f64>();
+        let longitude = eta_prime.sinh().atan2(xi_prime.cos());
+        LatitudeLongitudeCoordinates {
+            latitude: latitude.to_degrees(),
+            longitude: normalize_longitude(central_meridian(self.zone) + longitude.to_degrees()),
+        }
+    }
+}
+
+impl fmt::Display for Utm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let precision = f.precision().unwrap_or(0);
+        write!(
+            f,
+            "{}{} {:.*} {:.*}",
+            self.zone, self.band, precision, self.easting, precision, self.northing
+        )
+    }
+}
+
+impl FromStr for Utm {
+    type Err = ParseCoordinatesError;
+
+    /// Parses `31U 448251 5411932`, optionally with a space between the zone and the band and `mE`/`mN` units
+    /// (`31 U 448251mE 5411932mN`).
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let format_error = || ParseCoordinatesError::Format(s.into());
+        let tokens: Vec<&str> = s.split_whitespace().collect();
+        let (zone_band, easting, northing) = match tokens[..] {
+            [zone_band, easting, northing] => (zone_band.to_string(), easting, northing),
+            [zone, band, easting, northing] => (format!("{}{}", zone, band), easting, northing),
+            _ => return Err(format_error()),
+        };
+        let (zone, band) = parse_zone_band(&zone_band).ok_or_else(format_error)?;
+        let meters = |text: &str, unit: &str| {
+            text.trim_end_matches(unit)
+                .trim_end_matches(['m', 'M'])
+                .parse::<f64>()
+                .ok()
+                .filter(|meters| meters.is_finite())
+        };
+        let easting = meters(easting, "E").ok_or_else(format_error)?;
+        let northing = meters(northing, "N").ok_or_else(format_error)?;
+        if !(0.0..1_000_000.0).contains(&easting) || !(0.0..=UTM_FALSE_NORTHING).contains(&northing)
+        {
+            return Err(ParseCoordinatesError::OutOfRange(s.into()));
+        }
+        Ok(Utm {
+            zone,
+            band,
+            easting,
+            northing,
+        })
+    }
+}
+
+/// Military Grid Reference System coordinates: a UTM zone and latitude band, a 100km square identified by two
+/// letters and the position within that square.
+///
+/// Displayed as `31UDQ4825111932`, with the number of digits of the easting and northing as the precision (`{:.3}`
+/// for 100m, 5 digits - 1m - by default). Positions are truncated: the displayed position is the south-west corner of
+/// the cell containing the point.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Mgrs {
+    pub zone: u8,
+    pub band: char,
+    /// Letter of the 100km square column.
+    pub column: char,
+    /// Letter of the 100km square row.
+    pub row: char,
+    /// Easting within the 100km square, in meters.
+    pub easting: f64,
+    /// Northing within the 100km square, in meters.
+    pub northing: f64,
+}
+
+impl Mgrs {
+    pub fn from_utm(utm: &Utm) -> Mgrs {
+        let column = (utm.easting / MGRS_SQUARE).floor() as usize;
+        let row = (utm.northing / MGRS_SQUARE).floor() as usize;
+        let columns = MGRS_COLUMNS[(utm.zone as usize + 2) % 3];
+        Mgrs {
+            zone: utm.zone,
+            band: utm.band,
+            column: columns[(column + 7) % 8] as char,
+            row: MGRS_ROWS[(row + mgrs_row_offset(utm.zone)) % 20] as char,
+            easting: utm.easting % MGRS_SQUARE,
+            northing: utm.northing % MGRS_SQUARE,
+        }
+    }
+
+    /// MGRS coordinates of a lat/lon, `None` outside of the UTM latitude limits (80°S to 84°N).
+    pub fn from_latitude_longitude(coords: &LatitudeLongitudeCoordinates) -> Option<Mgrs> {
+        Utm::from_latitude_longitude(coords).map(|utm| Mgrs::from_utm(&utm))
+    }
+
+    /// The UTM coordinates, `None` if the 100km square letters don't exist in the zone.
+    pub fn to_utm(&self) -> Option<Utm> {
+        if !(1..=60).contains(&self.zone) || !UTM_BANDS.contains(&(self.band as u8)) {
+            return None;
+        }
+        let columns = MGRS_COLUMNS[(self.zone as usize + 2) % 3];
+        let column = columns.iter().position(|c| *c as char == self.column)? + 1;
+        let row = MGRS_ROWS.iter().position(|c| *c as char == self.row)?;
+        let row = (row + 20 - mgrs_row_offset(self.zone)) % 20;
+
+        // Rows repeat every 2000km, pick the repetition within the latitude band.
+        let band_latitude =
+            -80.0 + 8.0 * UTM_BANDS.iter().position(|c| *c as char == self.band)? as f64;
+        let (_, band_northing) =
+            transverse_mercator(band_latitude, central_meridian(self.zone), self.zone);
+        let band_northing = (band_northing / MGRS_SQUARE).floor() * MGRS_SQUARE;
+        let mut northing = row as f64 * MGRS_SQUARE + self.northing;
+        while northing < band_northing {
+            northing += 20.0 * MGRS_SQUARE;
+        }
+        Some(Utm {
+            zone: self.zone,
+            band: self.band,
+            easting: column as f64 * MGRS_SQUARE + self.easting,
+            northing,
+        })
+    }
+
+    /// The lat/lon of the coordinates, `None` if the 100km square letters don't exist in the zone.
+    pub fn to_latitude_longitude(&self) -> Option<LatitudeLongitudeCoordinates> {
+        self.to_utm().map(|utm| utm.to_latitude_longitude())
+    }
+}
+
+impl fmt::Display for Mgrs {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let digits = f.precision().unwrap_or(5).min(5);
+        write!(f, "{}{}{}{}", self.zone, self.band, self.column, self.row)?;
+        if digits == 0 {
+            // The whole 100km square.
+            return Ok(());
+        }
+        let cell = 10f64.powi(5 - digits as i32);
+        write!(
+            f,
+            "{:0digits$}{:0digits$}",
+            (self.easting / cell).floor() as u32,
+            (self.northing / cell).floor() as u32,
+            digits = digits,
+        )
+    }
+}
+
+impl FromStr for Mgrs {
+    type Err = ParseCoordinatesError;
+
+    /// Parses `31UDQ4825111932`, ignoring spaces and case (`31U DQ 48251 11932`). Both easting and northing must
+    /// have the same number of digits, from 0 (the whole 100km square) to 5 (1m).
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let format_error = || ParseCoordinatesError::Format(s.into());
+        let text: String = s
+            .chars()
+            .filter(|c| !c.is_whitespace())
+            .collect::<String>()
+            .to_ascii_uppercase();
+        let letters = text
+            .find(|c: char| c.is_ascii_alphabetic())
+            .ok_or_else(format_error)?;
+        let (zone, band) = parse_zone_band(text.get(..letters + 1).ok_or_else(format_error)?)
+            .ok_or_else(format_error)?;
+        let mut square = text[letters + 1..].chars();
+        let (column, row) = (
+            square.next().ok_or_else(format_error)?,
+            square.next().ok_or_else(format_error)?,
+        );
+        let digits = square.as_str();
+        if !digits.len().is_multiple_of(2)
+            || digits.len() > 10
+            || !digits.bytes().all(|c| c.is_ascii_digit())
+        {
+            return Err(format_error());
+        }
+        let (easting, northing) = digits.split_at(digits.len() / 2);
+        let cell = 10f64.powi(5 - easting.len() as i32);
+        let meters = |digits: &str| digits.parse::<f64>().unwrap_or_default() * cell;
+        let mgrs = Mgrs {
+            zone,
+            band,
+            column,
+            row,
+            easting: meters(easting),
+            northing: meters(northing),
+        };
+        match mgrs.to_utm() {
+            Some(_) => Ok(mgrs),
+            None => Err(ParseCoordinatesError::OutOfRange(s.into())),
+        }
+    }
+}
+
+impl LatitudeLongitudeCoordinates {
+    /// UTM coordinates of this point, `None` outside of the UTM latitude limits (80°S to 84°N).
+    pub fn to_utm(&self) -> Option<Utm> {
+        Utm::from_latitude_longitude(self)
+    }
+
+    /// MGRS coordinates of this point, `None` outside of the UTM latitude limits (80°S to 84°N).
+    pub fn to_mgrs(&self) -> Option<Mgrs> {
+        Mgrs::from_latitude_longitude(self)
+    }
+}
+
+// Latitude band letter of a latitude between 80°S and 84°N.
+fn utm_band(latitude: f64) -> char {
+    UTM_BANDS[(((latitude + 80.0) / 8.0).floor() as usize).min(19)] as char
+}
+
+fn central_meridian(zone: u8) -> f64 {
+    zone as f64 * 6.0 - 183.0
+}
+
+// The 100km square rows start 5 letters further in even zones.
+fn mgrs_row_offset(zone: u8) -> usize {
+    if zone.is_multiple_of(2) {
+        5
+    } else {
+        0
+    }
+}
+
+// A zone number (1 to 60) followed by a latitude band letter, like `31U`.
+fn parse_zone_band(text: &str) -> Option<(u8, char)> {
+    let text = text.to_ascii_uppercase();
+    let band = text.chars().last()?;
+    let zone = text.strip_suffix(band)?.parse::<u8>().ok()?;
+    ((1..=60).contains(&zone) && UTM_BANDS.contains(&(band as u8))).then_some((zone, band))
+}
+
+// Third flattening and rectifying radius of the WGS84 ellipsoid, used by the Krüger series.
+fn krueger_n_and_a() -> (f64, f64) {
+    let n = WGS84_FLATTENING / (2.0 - WGS84_FLATTENING);
+    let a = EARTH_RADIUS / (1.0 + n) * (1.0 + n.powi(2) / 4.0 + n.powi(4) / 64.0);
+    (n, a)
+}
+
+// Easting and northing of a lat/lon in the given UTM zone, using the Krüger series to the 4th order (accurate to
+// well under a millimeter within the zone):
+// https://en.wikipedia.org/wiki/Universal_Transverse_Mercator_coordinate_system#Simplified_formulae
+fn transverse_mercator(latitude: f64, longitude: f64, zone: u8) -> (f64, f64) {
+    let (n, a) = krueger_n_and_a();
+    let alpha = [
+        n / 2.0 - 2.0 / 3.0 * n.powi(2) + 5.0 / 16.0 * n.powi(3) + 41.0 / 180.0 * n.powi(4),
+        13.0 / 48.0 * n.powi(2) - 3.0 / 5.0 * n.powi(3) + 557.0 / 1440.0 * n.powi(4),
+        61.0 / 240.0 * n.powi(3) - 103.0 / 140.0 * n.powi(4),
+        49561.0 / 161280.0 * n.powi(4),
+    ];
+    let latitude = latitude.to_radians();
+    let d_longitude = normalize_longitude(longitude - central_meridian(zone)).to_radians();
+    let two_root_n = 2.0 * n.sqrt() / (1.0 + n);
+    let t = (latitude.sin().atanh() - two_root_n * (two_root_n * latitude.sin()).atanh()).sinh();
+    let xi_prime = t.atan2(d_longitude.cos());
+    let eta_prime = (d_longitude.sin() / (1.0 + t * t).sqrt()).atanh();
+    let (mut xi, mut eta) = (xi_prime, eta_prime);
+    for (j, alpha) in (1..=4).map(|j| j as f64 * 2.0).zip(alpha) {
+        xi += alpha * (j * xi_prime).sin() * (j * eta_prime).cosh();
+        eta += alpha * (j * xi_prime).cos() * (j * eta_prime).sinh();
+    }
+    let false_northing = if latitude < 0.0 {
+        UTM_FALSE_NORTHING
+    } else {
+        0.0
+    };
+    (
+        UTM_FALSE_EASTING + UTM_SCALE_FACTOR * a * eta,
+        false_northing + UTM_SCALE_FACTOR * a * xi,
+    )
+}
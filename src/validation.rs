@@ -0,0 +1,125 @@
+use bevy::{
+    asset::RenderAssetUsages,
+    image::{CompressedImageFormats, Image, ImageFormat, ImageSampler, ImageType},
+    prelude::Resource,
+};
+use std::sync::Arc;
+
+use crate::{TileResponse, TileSize, TileValidationError};
+
+/// Checks applied to downloaded tiles before they are written to the tiles directory.
+///
+/// Every check is disabled by default. Rejected responses fail with
+/// [`TileDownloadError::Invalid`](crate::TileDownloadError::Invalid) right away, without being retried (the tile server
+/// would most likely send the same response again).
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TileValidation {
+    /// Accepted `Content-Type`s (example: `image/png`, or `image/*` for any image), any content type if empty.
+    pub content_types: Vec<String>,
+    /// Minimum size of the response body, in bytes.
+    pub min_bytes: Option<usize>,
+    /// Maximum size of the response body, in bytes.
+    pub max_bytes: Option<usize>,
+    /// Decode the tile and check it matches the requested [`TileSize`].
+    ///
+    /// Tiles are decoded as PNG unless their `Content-Type` is another image type. Image types bevy can't decode with
+    /// its enabled features (JPEG needs bevy's `jpeg` feature, WebP its `webp` feature) are not checked, rather than
+    /// rejected.
+    pub check_dimensions: bool,
+}
+
+impl TileValidation {
+    /// Validates a 200 response to a request for a tile of the given size.
+    pub fn validate(
+        &self,
+        response: &TileResponse,
+        tile_size: TileSize,
+    ) -> Result<(), TileValidationError> {
+        let content_type = response
+            .header("Content-Type")
+            .map(|value| value.split(';').next().unwrap_or(value).trim());
+        if !self.content_types.is_empty()
+            && !content_type.is_some_and(|content_type| {
+                self.content_types
+                    .iter()
+                    .any(|accepted| content_type_matches(accepted, content_type))
+            })
+        {
+            return Err(TileValidationError::ContentType(
+                content_type.map(str::to_owned),
+            ));
+        }
+
+        let size = response.bytes.len();
+        if self.min_bytes.is_some_and(|min_bytes| size < min_bytes) {
+            return Err(TileValidationError::TooSmall(size));
+        }
+        if self.max_bytes.is_some_and(|max_bytes| size > max_bytes) {
+            return Err(TileValidationError::TooLarge(size));
+        }
+
+        // Tiles are PNGs unless the server says otherwise.
+        let image_type =
+            match content_type.filter(|content_type| content_type.starts_with("image/")) {
+                Some(content_type) => ImageFormat::from_mime_type(content_type)
+                    .map(|_| ImageType::MimeType(content_type)),
+                None => Some(ImageType::Extension("png")),
+            };
+        if let Some(image_type) = image_type.filter(|_| self.check_dimensions) {
+            let image = Image::from_buffer(
+                &response.bytes,
+                image_type,
+                CompressedImageFormats::NONE,
+                true,
+                ImageSampler::Default,
+                RenderAssetUsages::default(),
+            )
+            .map_err(|e| TileValidationError::Undecodable(e.to_string()))?;
+            let pixels = tile_size.to_pixels();
+            if image.width() != pixels || image.height() != pixels {
+                return Err(TileValidationError::Dimensions {
+                    width: image.width(),
+                    height: image.height(),
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+fn content_type_matches(accepted: &str, content_type: &str) -> bool {
+    match accepted.strip_suffix('*') {
+        Some(prefix) => content_type
+            .get(..prefix.len())
+            .is_some_and(|start| start.eq_ignore_ascii_case(prefix)),
+        None => accepted.eq_ignore_ascii_case(content_type),
+    }
+}
+
+/// Custom check run on downloaded tiles after the [`TileValidation`] checks, such as rejecting a known placeholder image.
+///
+/// Insert it as the [`SlippyTileValidator`] resource. Any closure taking a [`TileResponse`] and returning
+/// `Result<(), String>` can be used.
+pub trait TileValidator: Send + Sync + 'static {
+    /// Returns why the tile is rejected, if it is.
+    fn validate(&self, response: &TileResponse) -> Result<(), String>;
+}
+
+impl<F> TileValidator for F
+where
+    F: Fn(&TileResponse) -> Result<(), String> + Send + Sync + 'static,
+{
+    fn validate(&self, response: &TileResponse) -> Result<(), String> {
+        self(response)
+    }
+}
+
+/// The [`TileValidator`] applied to downloaded tiles, if any.
+#[derive(Resource, Clone, Default)]
+pub struct SlippyTileValidator(pub Option<Arc<dyn TileValidator>>);
+
+impl SlippyTileValidator {
+    pub fn new(validator: impl TileValidator) -> SlippyTileValidator {
+        SlippyTileValidator(Some(Arc::new(validator)))
+    }
+}
@@ -0,0 +1,108 @@
+use std::f64::consts::PI;
+
+use crate::{
+    constants::{EARTH_RADIUS, MAX_LATITUDE},
+    coordinates::{
+        max_pixels_in_dimension, FractionalTileCoordinates, LatitudeLongitudeCoordinates,
+    },
+    types::{TileSize, ZoomLevel},
+};
+
+/// Distance from the origin to the edges of the Web Mercator map, in meters (the map is twice as wide and high).
+pub const WEB_MERCATOR_EXTENT: f64 = PI * EARTH_RADIUS;
+
+/// Web Mercator (EPSG:3857) coordinates in meters, (0, 0) being where the equator meets the prime meridian.
+/// x grows eastward and y northward, both within ±[`WEB_MERCATOR_EXTENT`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WebMercatorCoordinates {
+    pub x: f64,
+    pub y: f64,
+}
+
+/// A bounding box in Web Mercator (EPSG:3857) meters.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WebMercatorBounds {
+    pub min_x: f64,
+    pub min_y: f64,
+    pub max_x: f64,
+    pub max_y: f64,
+}
+
+impl WebMercatorCoordinates {
+    /// Projects a real-world lat/lon, latitudes being clamped to the Web Mercator limits.
+    pub fn from_latitude_longitude(lat: f64, lon: f64) -> WebMercatorCoordinates {
+        let lat_rad = lat.clamp(-MAX_LATITUDE, MAX_LATITUDE).to_radians();
+        WebMercatorCoordinates {
+            x: EARTH_RADIUS * lon.to_radians(),
+            y: EARTH_RADIUS * (PI / 4.0 + lat_rad / 2.0).tan().ln(),
+        }
+    }
+
+    pub fn to_latitude_longitude(&self) -> LatitudeLongitudeCoordinates {
+        LatitudeLongitudeCoordinates {
+            latitude: (2.0 * (self.y / EARTH_RADIUS).exp().atan() - PI / 2.0).to_degrees(),
+            longitude: (self.x / EARTH_RADIUS).to_degrees(),
+        }
+    }
+
+    pub fn from_fractional_tile_coordinates(
+        coords: FractionalTileCoordinates,
+        zoom_level: ZoomLevel,
+    ) -> WebMercatorCoordinates {
+        let tile_meters = tile_meters(zoom_level);
+        WebMercatorCoordinates {
+            x: coords.x * tile_meters - WEB_MERCATOR_EXTENT,
+            y: WEB_MERCATOR_EXTENT - coords.y * tile_meters,
+        }
+    }
+
+    /// Get fractional tile coordinates (y growing southward) at the given zoom level.
+    pub fn to_fractional_tile_coordinates(
+        &self,
+        zoom_level: ZoomLevel,
+    ) -> FractionalTileCoordinates {
+        let tile_meters = tile_meters(zoom_level);
+        FractionalTileCoordinates {
+            x: (self.x + WEB_MERCATOR_EXTENT) / tile_meters,
+            y: (WEB_MERCATOR_EXTENT - self.y) / tile_meters,
+        }
+    }
+
+    /// Given a x and y pixel position in the world (0,0 at the bottom left, as with [`world_coords_to_world_pixel`](crate::world_coords_to_world_pixel)),
+    /// return the Web Mercator coordinates.
+    pub fn from_world_pixel(
+        x_pixel: f64,
+        y_pixel: f64,
+        tile_size: TileSize,
+        zoom_level: ZoomLevel,
+    ) -> WebMercatorCoordinates {
+        let meters_per_pixel =
+            2.0 * WEB_MERCATOR_EXTENT / max_pixels_in_dimension(zoom_level, tile_size);
+        WebMercatorCoordinates {
+            x: x_pixel * meters_per_pixel - WEB_MERCATOR_EXTENT,
+            y: y_pixel * meters_per_pixel - WEB_MERCATOR_EXTENT,
+        }
+    }
+
+    /// Get the x and y pixel position in the world (0,0 at the bottom left).
+    pub fn to_world_pixel(&self, tile_size: TileSize, zoom_level: ZoomLevel) -> (f64, f64) {
+        let pixels_per_meter =
+            max_pixels_in_dimension(zoom_level, tile_size) / (2.0 * WEB_MERCATOR_EXTENT);
+        (
+            (self.x + WEB_MERCATOR_EXTENT) * pixels_per_meter,
+            (self.y + WEB_MERCATOR_EXTENT) * pixels_per_meter,
+        )
+    }
+}
+
+impl LatitudeLongitudeCoordinates {
+    /// Get Web Mercator (EPSG:3857) coordinates, latitudes being clamped to the Web Mercator limits.
+    pub fn to_web_mercator(&self) -> WebMercatorCoordinates {
+        WebMercatorCoordinates::from_latitude_longitude(self.latitude, self.longitude)
+    }
+}
+
+// Width and height of a tile at the given zoom level, in Web Mercator meters.
+pub(crate) fn tile_meters(zoom_level: ZoomLevel) -> f64 {
+    2.0 * WEB_MERCATOR_EXTENT / (1u64 << zoom_level.to_u8()) as f64
+}